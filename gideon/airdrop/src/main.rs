@@ -0,0 +1,141 @@
+//! Builds the Merkle tree for a Gideon airdrop campaign from a CSV of recipients.
+//!
+//! The CSV has a `recipient,amount,expiry` header, with the amount in lamports and the
+//! expiry as a unix timestamp in seconds. The output JSON holds the root and totals to
+//! pass to `CreateCampaign`, and the proof for each recipient to pass to `ClaimVoucher`.
+//!
+//! ```sh
+//! cargo run --release -- recipients.csv campaign.json
+//! ```
+
+use std::{env, fs, process};
+
+use gideon::merkle;
+use serde::Serialize;
+use solana_program::pubkey::Pubkey;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Campaign {
+    merkle_root: [u8; 32],
+    leaf_count: u32,
+    total_amount: u64,
+    claims: Vec<Claim>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Claim {
+    index: u32,
+    recipient: String,
+    amount: u64,
+    expiry: i64,
+    proof: Vec<[u8; 32]>,
+}
+
+struct Entry {
+    recipient: Pubkey,
+    amount: u64,
+    expiry: i64,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!("Usage: {} <recipients.csv> [campaign.json]", args[0]);
+        process::exit(1);
+    }
+
+    let campaign = fs::read_to_string(&args[1])
+        .map_err(|err| format!("Failed to read {}: {}", args[1], err))
+        .and_then(|csv| parse_entries(&csv))
+        .and_then(|entries| build_campaign(&entries))
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+
+    let json = serde_json::to_string_pretty(&campaign).unwrap();
+    match args.get(2) {
+        Some(path) => fs::write(path, json).unwrap_or_else(|err| {
+            eprintln!("Failed to write {}: {}", path, err);
+            process::exit(1);
+        }),
+        None => println!("{}", json),
+    }
+}
+
+fn parse_entries(csv: &str) -> Result<Vec<Entry>, String> {
+    let mut lines = csv
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    match lines.next() {
+        Some((_, header)) if header.trim() == "recipient,amount,expiry" => {}
+        _ => return Err("Expected a recipient,amount,expiry header".to_string()),
+    }
+
+    lines
+        .map(|(number, line)| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [recipient, amount, expiry] = fields[..] else {
+                return Err(format!("Line {}: expected 3 fields", number + 1));
+            };
+
+            Ok(Entry {
+                recipient: recipient
+                    .parse()
+                    .map_err(|_| format!("Line {}: invalid recipient", number + 1))?,
+                amount: amount
+                    .parse()
+                    .map_err(|_| format!("Line {}: invalid amount", number + 1))?,
+                expiry: expiry
+                    .parse()
+                    .map_err(|_| format!("Line {}: invalid expiry", number + 1))?,
+            })
+        })
+        .collect()
+}
+
+fn build_campaign(entries: &[Entry]) -> Result<Campaign, String> {
+    if entries.is_empty() {
+        return Err("No recipients".to_string());
+    }
+    let leaf_count = u32::try_from(entries.len())
+        .ok()
+        .filter(|count| *count <= gideon::state::campaign::Campaign::MAX_LEAVES)
+        .ok_or("Too many recipients for one campaign")?;
+    let total_amount = entries
+        .iter()
+        .try_fold(0u64, |total, entry| total.checked_add(entry.amount))
+        .ok_or("Total amount overflows")?;
+
+    let leaves = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            merkle::leaf_hash(index as u32, &entry.recipient, entry.amount, entry.expiry)
+        })
+        .collect();
+    let tree = merkle::MerkleTree::new(leaves).ok_or("No recipients")?;
+
+    let claims = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| Claim {
+            index: index as u32,
+            recipient: entry.recipient.to_string(),
+            amount: entry.amount,
+            expiry: entry.expiry,
+            proof: tree.proof(index),
+        })
+        .collect();
+
+    Ok(Campaign {
+        merkle_root: tree.root(),
+        leaf_count,
+        total_amount,
+        claims,
+    })
+}
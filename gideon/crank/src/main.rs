@@ -0,0 +1,215 @@
+//! Reference crank for Gideon: reclaims expired voucher escrows and collects their bounties.
+//!
+//! Every interval it scans the program's escrow accounts and sends a `ReleaseExpiredEscrow`
+//! for each one past its settlement deadline that is not disputed or awaiting a delivery
+//! confirmation. The funds go to the escrow's payer or refund address and the voucher NFT
+//! is burned; the keypair only pays the transaction fees and receives the bounty. Escrows
+//! offering less than `min-bounty` lamports are skipped.
+//!
+//! ```sh
+//! cargo run --release -- <program-id> <keypair.json> [rpc-url] [min-bounty]
+//! ```
+
+use std::{
+    env, process, thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use gideon::{
+    instruction::GideonInstruction,
+    state::{
+        authority::MintAuthorityPda, config::Config, escrow::Escrow, milestone::MilestoneSchedule,
+    },
+};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::RpcFilterType,
+};
+use solana_sdk::{
+    borsh1::try_from_slice_unchecked,
+    commitment_config::CommitmentConfig,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    sysvar,
+    transaction::Transaction,
+};
+use spl_token_2022::{
+    extension::{
+        permanent_delegate::PermanentDelegate, BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::Mint,
+};
+
+const DEFAULT_RPC_URL: &str = "https://api.devnet.solana.com";
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 || args.len() > 5 {
+        eprintln!(
+            "Usage: {} <program-id> <keypair.json> [rpc-url] [min-bounty]",
+            args[0]
+        );
+        process::exit(1);
+    }
+
+    let program_id: Pubkey = args[1].parse().unwrap_or_else(|_| {
+        eprintln!("Invalid program id: {}", args[1]);
+        process::exit(1);
+    });
+    let cranker = read_keypair_file(&args[2]).unwrap_or_else(|err| {
+        eprintln!("Failed to read {}: {}", args[2], err);
+        process::exit(1);
+    });
+    let rpc_url = args.get(3).map_or(DEFAULT_RPC_URL, String::as_str);
+    let min_bounty: u64 = args
+        .get(4)
+        .map_or(Ok(0), |arg| arg.parse())
+        .unwrap_or_else(|_| {
+            eprintln!("Invalid min bounty");
+            process::exit(1);
+        });
+
+    let client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    println!("Cranking {} as {}", program_id, cranker.pubkey());
+
+    loop {
+        if let Err(err) = crank(&client, &program_id, &cranker, min_bounty) {
+            eprintln!("Crank failed: {}", err);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Reclaims every escrow that can be reclaimed right now, returning the number reclaimed.
+fn crank(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    cranker: &Keypair,
+    min_bounty: u64,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let accounts = client.get_program_accounts_with_config(
+        program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::DataSize(Escrow::ACCOUNT_SPACE as u64)]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    // The program checks expiry against the cluster clock, so a slightly early crank only
+    // costs a failed simulation
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let (config, _) = Pubkey::find_program_address(&[Config::SEED_PREFIX.as_bytes()], program_id);
+
+    let mut reclaimed = 0;
+    for (escrow_key, account) in accounts {
+        let Ok(escrow) = try_from_slice_unchecked::<Escrow>(&account.data) else {
+            continue;
+        };
+        if now <= escrow.settlement_deadline()
+            || escrow.disputed_at.is_some()
+            || escrow.fulfilment.is_some()
+            || escrow.crank_bounty < min_bounty
+        {
+            continue;
+        }
+
+        let instruction = match release_expired_escrow(
+            client,
+            program_id,
+            &config,
+            &escrow_key,
+            &escrow,
+            cranker,
+        ) {
+            Ok(instruction) => instruction,
+            Err(err) => {
+                eprintln!("Skipping {}: {}", escrow_key, err);
+                continue;
+            }
+        };
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&cranker.pubkey()),
+            &[cranker],
+            client.get_latest_blockhash()?,
+        );
+        match client.send_and_confirm_transaction(&transaction) {
+            Ok(signature) => {
+                println!(
+                    "Reclaimed {} for a bounty of {} lamports: {}",
+                    escrow_key, escrow.crank_bounty, signature
+                );
+                reclaimed += 1;
+            }
+            Err(err) => eprintln!("Failed to reclaim {}: {}", escrow_key, err),
+        }
+    }
+
+    Ok(reclaimed)
+}
+
+fn release_expired_escrow(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    config: &Pubkey,
+    escrow_key: &Pubkey,
+    escrow: &Escrow,
+    cranker: &Keypair,
+) -> Result<Instruction, Box<dyn std::error::Error>> {
+    // The voucher is burned through its permanent delegate, from whichever account holds it.
+    // If the holder already burned it, or it is a legacy voucher without a permanent
+    // delegate, the token account and mint authority are not used.
+    let mint_data = client.get_account_data(&escrow.voucher_mint)?;
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    let mint_authority = match mint.get_extension::<PermanentDelegate>() {
+        Ok(extension) => {
+            Option::<Pubkey>::from(extension.delegate).ok_or("Voucher has no permanent delegate")?
+        }
+        Err(_) => {
+            Pubkey::find_program_address(&[MintAuthorityPda::SEED_PREFIX.as_bytes()], program_id).0
+        }
+    };
+    let token_account = client
+        .get_token_largest_accounts(&escrow.voucher_mint)?
+        .into_iter()
+        .find(|balance| balance.amount.amount != "0")
+        .map(|balance| balance.address.parse())
+        .transpose()?
+        .unwrap_or(escrow.recipient);
+
+    let mut accounts = vec![
+        AccountMeta::new(cranker.pubkey(), true),
+        AccountMeta::new(*escrow_key, false),
+        AccountMeta::new(escrow.voucher_mint, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(*config, false),
+        AccountMeta::new(escrow.refund_address.unwrap_or(escrow.payer), false),
+        AccountMeta::new(token_account, false),
+        AccountMeta::new_readonly(mint_authority, false),
+        AccountMeta::new_readonly(spl_token_2022::id(), false),
+    ];
+    if escrow.milestone_count > 0 {
+        let (milestones, _) = Pubkey::find_program_address(
+            &[
+                MilestoneSchedule::SEED_PREFIX.as_bytes(),
+                escrow_key.as_ref(),
+            ],
+            program_id,
+        );
+        accounts.push(AccountMeta::new(milestones, false));
+    }
+
+    Ok(Instruction::new_with_borsh(
+        *program_id,
+        &GideonInstruction::ReleaseExpiredEscrow,
+        accounts,
+    ))
+}
@@ -34,6 +34,254 @@ pub enum GideonError {
     /// Voucher not expired
     #[error("Voucher Not Expired")]
     VoucherNotExpired,
+
+    /// Voucher not yet valid
+    #[error("Voucher Not Yet Valid")]
+    VoucherNotYetValid,
+
+    /// Invalid validity window
+    #[error("Invalid Validity Window")]
+    InvalidValidityWindow,
+
+    /// Invalid grace period
+    #[error("Invalid Grace Period")]
+    InvalidGracePeriod,
+
+    /// Invalid redemption configuration
+    #[error("Invalid Redemption Configuration")]
+    InvalidRedemptionConfig,
+
+    /// Invalid voucher holder
+    #[error("Invalid Voucher Holder")]
+    InvalidVoucherHolder,
+
+    /// Invalid signature
+    #[error("Invalid Signature")]
+    InvalidSignature,
+
+    /// Authorization expired
+    #[error("Authorization Expired")]
+    AuthorizationExpired,
+
+    /// Nonce already used
+    #[error("Nonce Already Used")]
+    NonceAlreadyUsed,
+
+    /// Invalid redemption amount
+    #[error("Invalid Redemption Amount")]
+    InvalidRedemptionAmount,
+
+    /// Invalid redemption code
+    #[error("Invalid Redemption Code")]
+    InvalidRedemptionCode,
+
+    /// Invalid payout account
+    #[error("Invalid Payout Account")]
+    InvalidPayoutAccount,
+
+    /// Invalid config account
+    #[error("Invalid Config Account")]
+    InvalidConfigAccount,
+
+    /// Invalid upgrade authority
+    #[error("Invalid Upgrade Authority")]
+    InvalidUpgradeAuthority,
+
+    /// Invalid admin
+    #[error("Invalid Admin")]
+    InvalidAdmin,
+
+    /// Invalid config setting
+    #[error("Invalid Config Setting")]
+    InvalidConfigSetting,
+
+    /// Voucher amount above the configured limit
+    #[error("Voucher Amount Too Large")]
+    VoucherAmountTooLarge,
+
+    /// Invalid treasury account
+    #[error("Invalid Treasury Account")]
+    InvalidTreasuryAccount,
+
+    /// Program paused
+    #[error("Program Paused")]
+    ProgramPaused,
+
+    /// Invalid issuer account
+    #[error("Invalid Issuer Account")]
+    InvalidIssuerAccount,
+
+    /// Signer not authorised for the issuer
+    #[error("Unauthorized Issuer Signer")]
+    UnauthorizedIssuerSigner,
+
+    /// Invalid issuer profile
+    #[error("Invalid Issuer Profile")]
+    InvalidIssuerProfile,
+
+    /// Escrow is frozen by an open dispute
+    #[error("Escrow Disputed")]
+    EscrowDisputed,
+
+    /// Escrow has no open dispute
+    #[error("Escrow Not Disputed")]
+    EscrowNotDisputed,
+
+    /// Invalid arbiter account or configuration
+    #[error("Invalid Arbiter")]
+    InvalidArbiter,
+
+    /// Dispute settlement does not add up to the escrowed amount
+    #[error("Invalid Dispute Settlement")]
+    InvalidDisputeSettlement,
+
+    /// Dispute timeout has not passed yet
+    #[error("Dispute Not Timed Out")]
+    DisputeNotTimedOut,
+
+    /// Dispute timeout has passed
+    #[error("Dispute Timed Out")]
+    DisputeTimedOut,
+
+    /// Invalid milestone schedule
+    #[error("Invalid Milestone Schedule")]
+    InvalidMilestoneSchedule,
+
+    /// Milestone missing, already settled or approved out of order
+    #[error("Invalid Milestone")]
+    InvalidMilestone,
+
+    /// Milestone deadline has not passed yet
+    #[error("Milestone Not Overdue")]
+    MilestoneNotOverdue,
+
+    /// Voucher is paid out by milestone approvals only
+    #[error("Voucher Released By Milestones")]
+    VoucherReleasedByMilestones,
+
+    /// Voucher is redeemed through confirmed deliveries only
+    #[error("Delivery Confirmation Required")]
+    DeliveryConfirmationRequired,
+
+    /// Voucher does not use delivery confirmation
+    #[error("Delivery Confirmation Not Required")]
+    DeliveryConfirmationNotRequired,
+
+    /// Invalid delivery confirmation window
+    #[error("Invalid Confirmation Window")]
+    InvalidConfirmationWindow,
+
+    /// A claimed fulfilment is awaiting confirmation
+    #[error("Fulfilment Pending")]
+    FulfilmentPending,
+
+    /// No fulfilment has been claimed
+    #[error("Fulfilment Not Claimed")]
+    FulfilmentNotClaimed,
+
+    /// Holder can still confirm the fulfilment
+    #[error("Confirmation Window Open")]
+    ConfirmationWindowOpen,
+
+    /// Invalid airdrop campaign
+    #[error("Invalid Campaign")]
+    InvalidCampaign,
+
+    /// Merkle proof does not match the campaign root
+    #[error("Invalid Merkle Proof")]
+    InvalidMerkleProof,
+
+    /// Campaign voucher has already been claimed
+    #[error("Voucher Already Claimed")]
+    VoucherAlreadyClaimed,
+
+    /// Campaign claim period is over
+    #[error("Campaign Closed")]
+    CampaignClosed,
+
+    /// Campaign claim period is not over yet
+    #[error("Campaign Still Open")]
+    CampaignStillOpen,
+
+    /// Invalid grant vault account or metadata
+    #[error("Invalid Grant Vault")]
+    InvalidGrantVault,
+
+    /// Crank bounty above the maximum
+    #[error("Invalid Crank Bounty")]
+    InvalidCrankBounty,
+
+    /// Refund account does not match the escrow's payer or refund address
+    #[error("Invalid Refund Account")]
+    InvalidRefundAccount,
+
+    /// The voucher's lifecycle does not allow this change of status
+    #[error("Invalid Status Transition")]
+    InvalidStatusTransition,
+
+    /// Voucher frozen by its issuer
+    #[error("Voucher Frozen")]
+    VoucherFrozen,
+
+    /// Voucher not frozen
+    #[error("Voucher Not Frozen")]
+    VoucherNotFrozen,
+
+    /// Voucher was not issued with freezing enabled
+    #[error("Voucher Not Freezable")]
+    VoucherNotFreezable,
+
+    /// Invalid fiat face value, currency or collateral
+    #[error("Invalid Fiat Terms")]
+    InvalidFiatTerms,
+
+    /// Price feed account does not match the voucher or is not a trading price account
+    #[error("Invalid Price Feed")]
+    InvalidPriceFeed,
+
+    /// Price feed not updated recently enough to settle against
+    #[error("Stale Price")]
+    StalePrice,
+
+    /// Price feed confidence interval too wide to settle against
+    #[error("Price Uncertain")]
+    PriceUncertain,
+
+    /// Fiat vouchers can only be redeemed by their holder
+    #[error("Fiat Redemption Unsupported")]
+    FiatRedemptionUnsupported,
+
+    /// Too many products, or products on a voucher redeemed without a merchant
+    #[error("Invalid Products")]
+    InvalidProducts,
+
+    /// Product-bound voucher redeemed without a product attestation
+    #[error("Product Attestation Required")]
+    ProductAttestationRequired,
+
+    /// Attested product is not one the voucher can buy
+    #[error("Product Not Allowed")]
+    ProductNotAllowed,
+
+    /// Merchant not registered with the voucher's issuer
+    #[error("Merchant Not Registered")]
+    MerchantNotRegistered,
+
+    /// External reference longer than the maximum
+    #[error("Invalid External Ref")]
+    InvalidExternalRef,
+
+    /// Recovered voucher sent to its payer or one of the issuer's own signers
+    #[error("Invalid Recovery Holder")]
+    InvalidRecoveryHolder,
+
+    /// Holder can no longer reject the fulfilment
+    #[error("Confirmation Window Closed")]
+    ConfirmationWindowClosed,
+
+    /// Voucher has already been disputed the maximum number of times
+    #[error("Too Many Disputes")]
+    TooManyDisputes,
 }
 
 impl From<GideonError> for ProgramError {
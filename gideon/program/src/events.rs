@@ -0,0 +1,231 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{log::sol_log_data, pubkey::Pubkey};
+
+/// Events emitted by the program as borsh-encoded `Program data:` log entries.
+///
+/// New variants must be appended so existing discriminators stay stable for indexers.
+/// Redemption `amount`s are the value released from escrow, `fee` of which went to the treasury.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub enum GideonEvent {
+    VoucherRedeemed {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        recipient: Pubkey,
+        amount: u64,
+        fee: u64,
+        redemptions_remaining: u16,
+    },
+    VoucherRedeemedByAuthorization {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        holder: Pubkey,
+        merchant: Pubkey,
+        amount: u64,
+        fee: u64,
+        nonce: u64,
+        redemptions_remaining: u16,
+    },
+    VoucherRedeemedWithCode {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        merchant: Pubkey,
+        amount: u64,
+        fee: u64,
+    },
+    VoucherIssued {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        issuer: Pubkey,
+        payer: Pubkey,
+        recipient: Pubkey,
+        amount: u64,
+        fee: u64,
+    },
+    PauseUpdated {
+        authority: Pubkey,
+        paused: u8,
+        timestamp: i64,
+    },
+    IssuerVerificationUpdated {
+        issuer: Pubkey,
+        verified: bool,
+    },
+    VoucherAuthorityMigrated {
+        voucher_mint: Pubkey,
+        issuer: Pubkey,
+        mint_authority: Pubkey,
+    },
+    DisputeOpened {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        opened_by: Pubkey,
+        timestamp: i64,
+    },
+    DisputeResolved {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        arbiter: Pubkey,
+        merchant: Pubkey,
+        to_payer: u64,
+        to_recipient: u64,
+        to_merchant: u64,
+        arbiter_fee: u64,
+    },
+    DisputeLapsed {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        valid_until: i64,
+    },
+    MilestoneApproved {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        index: u8,
+        recipient: Pubkey,
+        amount: u64,
+        fee: u64,
+    },
+    MilestoneReclaimed {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        index: u8,
+        amount: u64,
+    },
+    FulfilmentClaimed {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        merchant: Pubkey,
+        holder: Pubkey,
+        amount: u64,
+        confirm_by: i64,
+    },
+    FulfilmentReleased {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        merchant: Pubkey,
+        confirmed: bool,
+        amount: u64,
+        fee: u64,
+        redemptions_remaining: u16,
+    },
+    CampaignCreated {
+        campaign: Pubkey,
+        issuer: Pubkey,
+        merkle_root: [u8; 32],
+        leaf_count: u32,
+        total_amount: u64,
+        fee: u64,
+    },
+    VoucherClaimed {
+        campaign: Pubkey,
+        index: u32,
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        recipient: Pubkey,
+        amount: u64,
+    },
+    CampaignClosed {
+        campaign: Pubkey,
+        unclaimed_amount: u64,
+    },
+    GrantRedeemed {
+        issuer: Pubkey,
+        signer: Pubkey,
+        recipient: Pubkey,
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        amount: u64,
+        nonce: u64,
+        fee: u64,
+    },
+    EscrowReclaimed {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        refund: Pubkey,
+        cranker: Pubkey,
+        amount: u64,
+        bounty: u64,
+    },
+    VoucherFrozen {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        authority: Pubkey,
+        reason_code: u16,
+    },
+    VoucherThawed {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        authority: Pubkey,
+    },
+    VoucherRecovered {
+        escrow: Pubkey,
+        old_mint: Pubkey,
+        new_mint: Pubkey,
+        old_holder: Pubkey,
+        new_holder: Pubkey,
+        authority: Pubkey,
+    },
+    FiatRedemptionSettled {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        currency: [u8; 3],
+        face_value: u64,
+        price: i64,
+        expo: i32,
+        amount: u64,
+        excess: u64,
+    },
+    MerchantRegistered {
+        issuer: Pubkey,
+        merchant: Pubkey,
+    },
+    MerchantRemoved {
+        issuer: Pubkey,
+        merchant: Pubkey,
+    },
+    ProductRedeemed {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        issuer: Pubkey,
+        merchant: Pubkey,
+        product: [u8; 32],
+        amount: u64,
+    },
+    FulfilmentRejected {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        merchant: Pubkey,
+        holder: Pubkey,
+        amount: u64,
+    },
+    EscrowMigrated {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        issuer: Pubkey,
+        valid_until: i64,
+    },
+    /// Follows the issuance or redemption event it belongs to, when that carried a partner
+    /// reference
+    ExternalRefRecorded {
+        voucher_mint: Pubkey,
+        escrow: Pubkey,
+        external_ref: Vec<u8>,
+    },
+}
+
+impl GideonEvent {
+    pub fn emit(&self) {
+        sol_log_data(&[&borsh::to_vec(self).unwrap()]);
+    }
+
+    /// Emits `ExternalRefRecorded` for the event just emitted, if it had a reference.
+    pub fn emit_external_ref(voucher_mint: Pubkey, escrow: Pubkey, external_ref: Option<Vec<u8>>) {
+        if let Some(external_ref) = external_ref {
+            Self::ExternalRefRecorded {
+                voucher_mint,
+                escrow,
+                external_ref,
+            }
+            .emit();
+        }
+    }
+}
@@ -1,14 +1,34 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use shank::ShankInstruction;
 
-use crate::instructions::{escrow::InitEscrowArgs, mint::MintVoucherArgs};
+use crate::{
+    instructions::{
+        authorized::RedeemWithAuthorizationArgs,
+        campaign::{ClaimVoucherArgs, CreateCampaignArgs},
+        code::RedeemWithCodeArgs,
+        config::{InitConfigArgs, SetPauseArgs, TransferAdminArgs, UpdateConfigArgs},
+        dispute::ResolveDisputeArgs,
+        escrow::InitEscrowArgs,
+        freeze::FreezeVoucherArgs,
+        grant::{GrantVaultArgs, RedeemGrantArgs, WithdrawGrantVaultArgs},
+        issuer::{IssuerProfileArgs, VerifyIssuerArgs},
+        merchant::RegisterMerchantArgs,
+        milestone::{ApproveMilestoneArgs, ReclaimMilestoneArgs},
+        mint::MintVoucherArgs,
+        payout::SetPayoutAddressArgs,
+        refund::SetRefundAddressArgs,
+    },
+    state::config::Config,
+};
 
 #[derive(BorshDeserialize, BorshSerialize, Clone, Debug, ShankInstruction)]
 #[rustfmt::skip]
+#[allow(clippy::large_enum_variant)]
 pub enum GideonInstruction {
     #[account(0, writable, name = "mint_authority", desc = "The account of the authority PDA")]
     #[account(1, writable, name = "payer", desc = "The account to pay for the authority PDA")]
     #[account(2, name = "system_program", desc = "The system program account")]
+    #[account(3, name = "issuer", desc = "The issuer account the authority mints for")]
     InitMintAuthority,
 
     #[account(0, writable, name = "escrow_account", desc = "The escrow account")]
@@ -20,9 +40,14 @@ pub enum GideonInstruction {
     #[account(6, name = "system_program", desc = "The system program account")]
     #[account(7, name = "token_program", desc = "The token program account")]
     #[account(8, name = "associated_token_program", desc = "The associated token program account")]
+    #[account(9, name = "config", desc = "The program config PDA")]
+    #[account(10, writable, name = "treasury", desc = "The platform treasury, paid the protocol fee")]
+    #[account(11, name = "issuer", desc = "The issuer account of the brand issuing the voucher")]
+    #[account(12, optional, writable, name = "milestones", desc = "The milestone schedule PDA, only for milestone escrows")]
+    #[account(13, optional, name = "price_feed", desc = "The price feed of a fiat voucher, passed in place of the milestones")]
     InitEscrowAndMintVoucher(InitEscrowArgs, MintVoucherArgs),
 
-    #[account(0, writable, signer, name = "payer", desc = "The account to pay for the burn")]
+    #[account(0, writable, signer, name = "payer", desc = "The voucher recipient or their approved delegate")]
     #[account(1, writable, name = "ata", desc = "The associated token account")]
     #[account(2, writable, name = "mint_account", desc = "The mint account")]
     #[account(3, name = "mint_authority", desc = "The authority over the mint")]
@@ -30,11 +55,426 @@ pub enum GideonInstruction {
     #[account(5, name = "token_program", desc = "The token program account")]
     #[account(6, name = "clock_program", desc = "The clock program account")]
     #[account(7, name = "system_program", desc = "The system program account")]
+    #[account(8, writable, name = "escrow_payer", desc = "The original payer of the escrow, refunded the rent deposit")]
+    #[account(9, writable, name = "payout", desc = "The recipient's payout address, or the recipient")]
+    #[account(10, name = "config", desc = "The program config PDA")]
+    #[account(11, writable, name = "treasury", desc = "The platform treasury, paid the protocol fee")]
+    #[account(12, writable, name = "receipt", desc = "The redemption receipt PDA, created for this redemption")]
+    #[account(13, optional, name = "price_feed", desc = "The price feed of a fiat voucher, read to settle its face value")]
     ReleaseEscrowAndBurnVoucher,
 
-    #[account(0, writable, signer, name = "payer", desc = "The account to pay for the release")]
+    #[account(0, writable, signer, name = "cranker", desc = "Anyone reclaiming the expired escrow, paid the crank bounty")]
     #[account(1, writable, name = "escrow_account", desc = "The escrow account")]
-    #[account(2, name = "mint_account", desc = "The mint account")]
+    #[account(2, writable, name = "mint_account", desc = "The mint account")]
     #[account(3, name = "clock_program", desc = "The clock program account")]
+    #[account(4, name = "config", desc = "The program config PDA")]
+    #[account(5, writable, name = "refund", desc = "The escrow's refund address, or its original payer")]
+    #[account(6, writable, name = "token_account", desc = "The token account holding the voucher, burned through the permanent delegate")]
+    #[account(7, name = "mint_authority", desc = "The mint authority PDA, permanent delegate of the voucher")]
+    #[account(8, name = "token_program", desc = "The token program account")]
+    #[account(9, optional, writable, name = "milestones", desc = "The milestone schedule PDA, only for milestone escrows")]
     ReleaseExpiredEscrow,
+
+    #[account(0, writable, signer, name = "payer", desc = "The voucher recipient or their approved delegate")]
+    #[account(1, writable, name = "ata", desc = "The associated token account")]
+    #[account(2, writable, name = "mint_account", desc = "The mint account")]
+    #[account(3, name = "mint_authority", desc = "The authority over the mint")]
+    #[account(4, writable, name = "escrow_account", desc = "The escrow account")]
+    #[account(5, name = "token_program", desc = "The token program account")]
+    #[account(6, name = "clock_program", desc = "The clock program account")]
+    #[account(7, name = "system_program", desc = "The system program account")]
+    #[account(8, writable, name = "escrow_payer", desc = "The original payer of the escrow, refunded the rent deposit")]
+    #[account(9, writable, name = "payout", desc = "The recipient's payout address, or the recipient")]
+    #[account(10, name = "config", desc = "The program config PDA")]
+    #[account(11, writable, name = "treasury", desc = "The platform treasury, paid the protocol fee")]
+    #[account(12, writable, name = "receipt", desc = "The redemption receipt PDA, created for this redemption")]
+    #[account(13, optional, name = "price_feed", desc = "The price feed of a fiat voucher, read to settle its face value")]
+    Redeem,
+
+    #[account(0, writable, signer, name = "merchant", desc = "The merchant submitting the redemption, paid the redeemed value")]
+    #[account(1, name = "holder", desc = "The voucher holder who signed the authorization")]
+    #[account(2, writable, name = "ata", desc = "The holder's associated token account")]
+    #[account(3, writable, name = "mint_account", desc = "The mint account")]
+    #[account(4, name = "mint_authority", desc = "The authority over the mint")]
+    #[account(5, writable, name = "escrow_account", desc = "The escrow account")]
+    #[account(6, writable, name = "nonce_account", desc = "The replay-protection nonce PDA for the voucher")]
+    #[account(7, writable, name = "escrow_payer", desc = "The original payer of the escrow, refunded the rent deposit")]
+    #[account(8, name = "instructions_sysvar", desc = "The instructions sysvar account")]
+    #[account(9, name = "token_program", desc = "The token program account")]
+    #[account(10, name = "system_program", desc = "The system program account")]
+    #[account(11, name = "config", desc = "The program config PDA")]
+    #[account(12, writable, name = "treasury", desc = "The platform treasury, paid the protocol fee")]
+    #[account(13, writable, name = "receipt", desc = "The redemption receipt PDA, created for this redemption")]
+    #[account(14, optional, name = "merchant_registration", desc = "The merchant's registration with the issuer, when attesting a product")]
+    RedeemWithAuthorization(RedeemWithAuthorizationArgs),
+
+    #[account(0, writable, signer, name = "merchant", desc = "The merchant submitting the code, paid the redeemed value")]
+    #[account(1, writable, name = "ata", desc = "The token account holding the voucher")]
+    #[account(2, writable, name = "mint_account", desc = "The mint account")]
+    #[account(3, name = "mint_authority", desc = "The authority over the mint")]
+    #[account(4, writable, name = "escrow_account", desc = "The escrow account")]
+    #[account(5, writable, name = "escrow_payer", desc = "The original payer of the escrow, refunded the rent deposit")]
+    #[account(6, name = "token_program", desc = "The token program account")]
+    #[account(7, name = "config", desc = "The program config PDA")]
+    #[account(8, writable, name = "treasury", desc = "The platform treasury, paid the protocol fee")]
+    #[account(9, writable, name = "receipt", desc = "The redemption receipt PDA, created for this redemption")]
+    #[account(10, name = "system_program", desc = "The system program account")]
+    #[account(11, name = "merchant_registration", desc = "The merchant's registration with the issuer")]
+    RedeemWithCode(RedeemWithCodeArgs),
+
+    #[account(0, signer, name = "recipient", desc = "The voucher recipient")]
+    #[account(1, writable, name = "escrow_account", desc = "The escrow account")]
+    SetPayoutAddress(SetPayoutAddressArgs),
+
+    #[account(0, writable, name = "config", desc = "The program config PDA")]
+    #[account(1, writable, signer, name = "upgrade_authority", desc = "The program's upgrade authority, pays for the config")]
+    #[account(2, name = "program_data", desc = "The program's upgradeable loader data account")]
+    #[account(3, name = "system_program", desc = "The system program account")]
+    InitConfig(InitConfigArgs),
+
+    #[account(0, writable, name = "config", desc = "The program config PDA")]
+    #[account(1, signer, name = "admin", desc = "The config admin")]
+    UpdateConfig(UpdateConfigArgs),
+
+    #[account(0, writable, name = "config", desc = "The program config PDA")]
+    #[account(1, signer, name = "admin", desc = "The config admin")]
+    TransferAdmin(TransferAdminArgs),
+
+    #[account(0, writable, name = "config", desc = "The program config PDA")]
+    #[account(1, signer, name = "new_admin", desc = "The pending admin accepting the handover")]
+    AcceptAdmin,
+
+    #[account(0, writable, name = "config", desc = "The program config PDA")]
+    #[account(1, signer, name = "authority", desc = "The config admin or guardian")]
+    SetPause(SetPauseArgs),
+
+    #[account(0, writable, name = "issuer", desc = "The issuer PDA for the authority")]
+    #[account(1, writable, signer, name = "authority", desc = "The brand authority, pays for the issuer")]
+    #[account(2, name = "system_program", desc = "The system program account")]
+    RegisterIssuer(IssuerProfileArgs),
+
+    #[account(0, writable, name = "issuer", desc = "The issuer PDA")]
+    #[account(1, signer, name = "authority", desc = "The brand authority")]
+    UpdateIssuer(IssuerProfileArgs),
+
+    #[account(0, writable, name = "issuer", desc = "The issuer PDA")]
+    #[account(1, name = "config", desc = "The program config PDA")]
+    #[account(2, signer, name = "admin", desc = "The config admin")]
+    VerifyIssuer(VerifyIssuerArgs),
+
+    #[account(0, signer, name = "authority", desc = "The issuer's authority or one of its signers")]
+    #[account(1, name = "issuer", desc = "The issuer account of the voucher")]
+    #[account(2, writable, name = "escrow_account", desc = "The escrow account, paid for by the issuer authority")]
+    #[account(3, writable, name = "mint_account", desc = "The mint account")]
+    #[account(4, name = "legacy_mint_authority", desc = "The global mint authority the voucher was minted under")]
+    #[account(5, name = "mint_authority", desc = "The issuer's mint authority")]
+    #[account(6, name = "token_program", desc = "The token program account")]
+    MigrateMintAuthority,
+
+    #[account(0, signer, name = "party", desc = "The voucher payer or recipient raising the dispute")]
+    #[account(1, writable, name = "escrow_account", desc = "The escrow account")]
+    OpenDispute,
+
+    #[account(0, writable, signer, name = "arbiter", desc = "The voucher arbiter, paid the arbiter fee")]
+    #[account(1, writable, name = "escrow_account", desc = "The escrow account")]
+    #[account(2, writable, name = "escrow_payer", desc = "The original payer of the escrow, paid its share and the rent deposit")]
+    #[account(3, writable, name = "payout", desc = "The recipient's payout address, or the recipient")]
+    #[account(4, writable, name = "merchant", desc = "The merchant paid its share of the settlement")]
+    #[account(5, writable, name = "mint_account", desc = "The mint account")]
+    #[account(6, writable, name = "token_account", desc = "The token account holding the voucher, burned through the permanent delegate")]
+    #[account(7, name = "mint_authority", desc = "The mint authority PDA, permanent delegate of the voucher")]
+    #[account(8, name = "token_program", desc = "The token program account")]
+    #[account(9, name = "config", desc = "The program config PDA")]
+    #[account(10, optional, writable, name = "milestones", desc = "The milestone schedule PDA, only for milestone escrows")]
+    ResolveDispute(ResolveDisputeArgs),
+
+    #[account(0, writable, name = "escrow_account", desc = "The escrow account")]
+    #[account(1, name = "config", desc = "The program config PDA")]
+    #[account(2, optional, writable, name = "milestones", desc = "The milestone schedule PDA, only for milestone escrows")]
+    LapseDispute,
+
+    #[account(0, writable, signer, name = "payer", desc = "The escrow payer approving the deliverable")]
+    #[account(1, writable, name = "escrow_account", desc = "The escrow account")]
+    #[account(2, writable, name = "milestones", desc = "The milestone schedule PDA")]
+    #[account(3, writable, name = "payout", desc = "The recipient's payout address, or the recipient")]
+    #[account(4, name = "config", desc = "The program config PDA")]
+    #[account(5, writable, name = "treasury", desc = "The platform treasury, paid the protocol fee")]
+    #[account(6, writable, name = "receipt", desc = "The redemption receipt PDA, created for this redemption")]
+    #[account(7, name = "system_program", desc = "The system program account")]
+    #[account(8, writable, name = "mint_account", desc = "The mint account")]
+    #[account(9, writable, name = "token_account", desc = "The token account holding the voucher, burned through the permanent delegate once every tranche is settled")]
+    #[account(10, name = "mint_authority", desc = "The mint authority PDA, permanent delegate of the voucher")]
+    #[account(11, name = "token_program", desc = "The token program account")]
+    ApproveMilestone(ApproveMilestoneArgs),
+
+    #[account(0, writable, signer, name = "payer", desc = "The escrow payer reclaiming the tranche")]
+    #[account(1, writable, name = "escrow_account", desc = "The escrow account")]
+    #[account(2, writable, name = "milestones", desc = "The milestone schedule PDA")]
+    #[account(3, name = "config", desc = "The program config PDA")]
+    #[account(4, writable, name = "mint_account", desc = "The mint account")]
+    #[account(5, writable, name = "token_account", desc = "The token account holding the voucher, burned through the permanent delegate once every tranche is settled")]
+    #[account(6, name = "mint_authority", desc = "The mint authority PDA, permanent delegate of the voucher")]
+    #[account(7, name = "token_program", desc = "The token program account")]
+    ReclaimMilestone(ReclaimMilestoneArgs),
+
+    #[account(0, signer, name = "merchant", desc = "The merchant claiming to have fulfilled the voucher")]
+    #[account(1, name = "ata", desc = "The token account holding the voucher")]
+    #[account(2, name = "mint_account", desc = "The mint account")]
+    #[account(3, writable, name = "escrow_account", desc = "The escrow account")]
+    #[account(4, name = "config", desc = "The program config PDA")]
+    #[account(5, name = "merchant_registration", desc = "The merchant's registration with the voucher's issuer")]
+    ClaimFulfilment,
+
+    #[account(0, writable, signer, name = "authority", desc = "The holder confirming receipt, or anyone once the confirmation window has passed, paying for the receipt")]
+    #[account(1, writable, name = "ata", desc = "The token account holding the voucher")]
+    #[account(2, writable, name = "mint_account", desc = "The mint account")]
+    #[account(3, name = "mint_authority", desc = "The authority over the mint")]
+    #[account(4, writable, name = "escrow_account", desc = "The escrow account")]
+    #[account(5, writable, name = "merchant", desc = "The merchant that claimed the fulfilment")]
+    #[account(6, writable, name = "escrow_payer", desc = "The original payer of the escrow, refunded the rent deposit")]
+    #[account(7, name = "token_program", desc = "The token program account")]
+    #[account(8, name = "config", desc = "The program config PDA")]
+    #[account(9, writable, name = "treasury", desc = "The platform treasury, paid the protocol fee")]
+    #[account(10, writable, name = "receipt", desc = "The redemption receipt PDA, created for this redemption")]
+    #[account(11, name = "system_program", desc = "The system program account")]
+    ReleaseFulfilment,
+
+    #[account(0, writable, name = "campaign", desc = "The campaign PDA, which also holds the vault")]
+    #[account(1, writable, signer, name = "authority", desc = "The issuer signer funding the campaign")]
+    #[account(2, name = "issuer", desc = "The issuer account of the brand running the campaign")]
+    #[account(3, name = "config", desc = "The program config PDA")]
+    #[account(4, writable, name = "treasury", desc = "The platform treasury, paid the protocol fee")]
+    #[account(5, name = "system_program", desc = "The system program account")]
+    CreateCampaign(CreateCampaignArgs),
+
+    #[account(0, writable, name = "escrow_account", desc = "The escrow account")]
+    #[account(1, writable, signer, name = "recipient", desc = "The recipient claiming the voucher, pays for the mint")]
+    #[account(2, writable, signer, name = "mint_account", desc = "The mint account to mint the NFT")]
+    #[account(3, writable, name = "mint_authority", desc = "The authority over the mint")]
+    #[account(4, writable, name = "associated_token_account", desc = "The recipient's associated token account")]
+    #[account(5, name = "rent", desc = "The rent account")]
+    #[account(6, name = "system_program", desc = "The system program account")]
+    #[account(7, name = "token_program", desc = "The token program account")]
+    #[account(8, name = "associated_token_program", desc = "The associated token program account")]
+    #[account(9, name = "config", desc = "The program config PDA")]
+    #[account(10, writable, name = "campaign", desc = "The campaign PDA")]
+    ClaimVoucher(ClaimVoucherArgs),
+
+    #[account(0, writable, signer, name = "authority", desc = "The authority that funded the campaign")]
+    #[account(1, writable, name = "campaign", desc = "The campaign PDA")]
+    #[account(2, name = "config", desc = "The program config PDA")]
+    CloseCampaign,
+
+    #[account(0, writable, name = "grant_vault", desc = "The issuer's grant vault PDA")]
+    #[account(1, writable, signer, name = "authority", desc = "The issuer authority, pays for the vault")]
+    #[account(2, name = "issuer", desc = "The issuer account")]
+    #[account(3, name = "system_program", desc = "The system program account")]
+    InitGrantVault(GrantVaultArgs),
+
+    #[account(0, writable, name = "grant_vault", desc = "The issuer's grant vault PDA")]
+    #[account(1, writable, signer, name = "authority", desc = "The issuer authority, paid the withdrawn funds")]
+    #[account(2, name = "issuer", desc = "The issuer account")]
+    #[account(3, name = "config", desc = "The program config PDA")]
+    WithdrawGrantVault(WithdrawGrantVaultArgs),
+
+    #[account(0, writable, name = "escrow_account", desc = "The escrow account")]
+    #[account(1, writable, signer, name = "recipient", desc = "The recipient named in the grant, pays for the mint")]
+    #[account(2, writable, signer, name = "mint_account", desc = "The mint account to mint the NFT")]
+    #[account(3, writable, name = "mint_authority", desc = "The authority over the mint")]
+    #[account(4, writable, name = "associated_token_account", desc = "The recipient's associated token account")]
+    #[account(5, name = "rent", desc = "The rent account")]
+    #[account(6, name = "system_program", desc = "The system program account")]
+    #[account(7, name = "token_program", desc = "The token program account")]
+    #[account(8, name = "associated_token_program", desc = "The associated token program account")]
+    #[account(9, name = "config", desc = "The program config PDA")]
+    #[account(10, writable, name = "treasury", desc = "The platform treasury, paid the protocol fee")]
+    #[account(11, name = "issuer", desc = "The issuer account that granted the voucher")]
+    #[account(12, name = "issuer_signer", desc = "The issuer key that signed the grant")]
+    #[account(13, writable, name = "grant_vault", desc = "The issuer's grant vault PDA")]
+    #[account(14, writable, name = "nonce_account", desc = "The PDA marking the grant nonce as used")]
+    #[account(15, name = "instructions_sysvar", desc = "The instructions sysvar account")]
+    RedeemGrant(RedeemGrantArgs),
+
+    #[account(0, signer, name = "payer", desc = "The original payer of the escrow")]
+    #[account(1, writable, name = "escrow_account", desc = "The escrow account")]
+    SetRefundAddress(SetRefundAddressArgs),
+
+    #[account(0, signer, name = "authority", desc = "A signer of the voucher's issuer, or the platform admin")]
+    #[account(1, writable, name = "escrow_account", desc = "The escrow account")]
+    #[account(2, name = "mint_account", desc = "The voucher mint account")]
+    #[account(3, writable, name = "token_account", desc = "The token account holding the voucher")]
+    #[account(4, name = "mint_authority", desc = "The mint authority PDA, freeze authority of the voucher")]
+    #[account(5, name = "token_program", desc = "The token program account")]
+    #[account(6, name = "issuer", desc = "The issuer account of the voucher")]
+    #[account(7, name = "config", desc = "The program config PDA")]
+    FreezeVoucher(FreezeVoucherArgs),
+
+    #[account(0, signer, name = "authority", desc = "A signer of the voucher's issuer, or the platform admin")]
+    #[account(1, writable, name = "escrow_account", desc = "The escrow account")]
+    #[account(2, name = "mint_account", desc = "The voucher mint account")]
+    #[account(3, writable, name = "token_account", desc = "The token account holding the voucher")]
+    #[account(4, name = "mint_authority", desc = "The mint authority PDA, freeze authority of the voucher")]
+    #[account(5, name = "token_program", desc = "The token program account")]
+    #[account(6, name = "issuer", desc = "The issuer account of the voucher")]
+    #[account(7, name = "config", desc = "The program config PDA")]
+    ThawVoucher,
+
+    #[account(0, writable, name = "escrow_account", desc = "The escrow account backing the lost voucher")]
+    #[account(1, writable, signer, name = "authority", desc = "A signer of the voucher's issuer, pays for the replacement")]
+    #[account(2, writable, signer, name = "mint_account", desc = "The mint account of the replacement voucher")]
+    #[account(3, writable, name = "mint_authority", desc = "The issuer's mint authority PDA")]
+    #[account(4, writable, name = "associated_token_account", desc = "The new holder's associated token account")]
+    #[account(5, name = "rent", desc = "The rent account")]
+    #[account(6, name = "system_program", desc = "The system program account")]
+    #[account(7, name = "token_program", desc = "The token program account")]
+    #[account(8, name = "associated_token_program", desc = "The associated token program account")]
+    #[account(9, name = "new_holder", desc = "The wallet receiving the replacement voucher")]
+    #[account(10, writable, name = "old_mint_account", desc = "The mint account of the lost voucher")]
+    #[account(11, writable, name = "old_token_account", desc = "The token account holding the lost voucher")]
+    #[account(12, name = "issuer", desc = "The issuer account of the voucher")]
+    #[account(13, name = "config", desc = "The program config PDA")]
+    #[account(14, optional, signer, name = "admin", desc = "The config admin, when recovery requires their signature")]
+    RecoverVoucher,
+
+    #[account(0, writable, name = "merchant_account", desc = "The merchant registration PDA")]
+    #[account(1, writable, signer, name = "authority", desc = "The issuer authority, paying for the registration")]
+    #[account(2, name = "issuer", desc = "The issuer account")]
+    #[account(3, name = "system_program", desc = "The system program account")]
+    RegisterMerchant(RegisterMerchantArgs),
+
+    #[account(0, writable, name = "merchant_account", desc = "The merchant registration PDA")]
+    #[account(1, writable, signer, name = "authority", desc = "The issuer authority, refunded the rent")]
+    #[account(2, name = "issuer", desc = "The issuer account")]
+    #[account(3, name = "merchant", desc = "The merchant being removed")]
+    RemoveMerchant,
+
+    #[account(0, signer, name = "holder", desc = "The holder the fulfilment was claimed for")]
+    #[account(1, writable, name = "escrow_account", desc = "The escrow account")]
+    RejectFulfilment,
+
+    #[account(0, writable, signer, name = "funder", desc = "Anyone paying the rent for the larger escrow")]
+    #[account(1, writable, name = "escrow_account", desc = "The escrow account in the legacy layout")]
+    #[account(2, name = "mint_account", desc = "The mint account")]
+    #[account(3, name = "system_program", desc = "The system program account")]
+    #[account(4, optional, name = "issuer", desc = "The issuer account of the escrow payer, if they have one")]
+    MigrateEscrow,
+}
+
+impl GideonInstruction {
+    /// The pause flag that disables this instruction, if it can be paused.
+    pub fn pause_flag(&self) -> Option<u8> {
+        match self {
+            Self::InitEscrowAndMintVoucher(..)
+            | Self::CreateCampaign(..)
+            | Self::ClaimVoucher(..)
+            | Self::RedeemGrant(..)
+            | Self::RecoverVoucher => Some(Config::PAUSE_ISSUANCE),
+            Self::ReleaseEscrowAndBurnVoucher
+            | Self::Redeem
+            | Self::RedeemWithAuthorization(..)
+            | Self::RedeemWithCode(..)
+            | Self::ApproveMilestone(..)
+            | Self::ClaimFulfilment
+            | Self::ReleaseFulfilment
+            | Self::ResolveDispute(..)
+            | Self::LapseDispute => Some(Config::PAUSE_REDEMPTION),
+            Self::ReleaseExpiredEscrow
+            | Self::ReclaimMilestone(..)
+            | Self::CloseCampaign
+            | Self::WithdrawGrantVault(..) => Some(Config::PAUSE_RECLAIM),
+            Self::InitMintAuthority
+            | Self::SetPayoutAddress(..)
+            | Self::InitConfig(..)
+            | Self::UpdateConfig(..)
+            | Self::TransferAdmin(..)
+            | Self::AcceptAdmin
+            | Self::SetPause(..)
+            | Self::RegisterIssuer(..)
+            | Self::UpdateIssuer(..)
+            | Self::VerifyIssuer(..)
+            | Self::MigrateMintAuthority
+            | Self::OpenDispute
+            | Self::InitGrantVault(..)
+            | Self::SetRefundAddress(..)
+            | Self::FreezeVoucher(..)
+            | Self::ThawVoucher
+            | Self::RegisterMerchant(..)
+            | Self::RemoveMerchant
+            | Self::RejectFulfilment
+            | Self::MigrateEscrow => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The pause group of every instruction, written out independently of `pause_flag`.
+    const PAUSE_FLAGS: &[(&str, Option<u8>)] = &[
+        ("InitMintAuthority", None),
+        ("InitEscrowAndMintVoucher", Some(Config::PAUSE_ISSUANCE)),
+        (
+            "ReleaseEscrowAndBurnVoucher",
+            Some(Config::PAUSE_REDEMPTION),
+        ),
+        ("ReleaseExpiredEscrow", Some(Config::PAUSE_RECLAIM)),
+        ("Redeem", Some(Config::PAUSE_REDEMPTION)),
+        ("RedeemWithAuthorization", Some(Config::PAUSE_REDEMPTION)),
+        ("RedeemWithCode", Some(Config::PAUSE_REDEMPTION)),
+        ("SetPayoutAddress", None),
+        ("InitConfig", None),
+        ("UpdateConfig", None),
+        ("TransferAdmin", None),
+        ("AcceptAdmin", None),
+        ("SetPause", None),
+        ("RegisterIssuer", None),
+        ("UpdateIssuer", None),
+        ("VerifyIssuer", None),
+        ("MigrateMintAuthority", None),
+        ("OpenDispute", None),
+        ("ResolveDispute", Some(Config::PAUSE_REDEMPTION)),
+        ("LapseDispute", Some(Config::PAUSE_REDEMPTION)),
+        ("ApproveMilestone", Some(Config::PAUSE_REDEMPTION)),
+        ("ReclaimMilestone", Some(Config::PAUSE_RECLAIM)),
+        ("ClaimFulfilment", Some(Config::PAUSE_REDEMPTION)),
+        ("ReleaseFulfilment", Some(Config::PAUSE_REDEMPTION)),
+        ("CreateCampaign", Some(Config::PAUSE_ISSUANCE)),
+        ("ClaimVoucher", Some(Config::PAUSE_ISSUANCE)),
+        ("CloseCampaign", Some(Config::PAUSE_RECLAIM)),
+        ("InitGrantVault", None),
+        ("WithdrawGrantVault", Some(Config::PAUSE_RECLAIM)),
+        ("RedeemGrant", Some(Config::PAUSE_ISSUANCE)),
+        ("SetRefundAddress", None),
+        ("FreezeVoucher", None),
+        ("ThawVoucher", None),
+        ("RecoverVoucher", Some(Config::PAUSE_ISSUANCE)),
+        ("RegisterMerchant", None),
+        ("RemoveMerchant", None),
+        ("RejectFulfilment", None),
+        ("MigrateEscrow", None),
+    ];
+
+    #[test]
+    fn pause_flags_cover_every_instruction() {
+        // Zeroed arguments decode for every variant, so walking the discriminants visits
+        // each instruction once, in declaration order
+        let mut data = vec![0; 1024];
+        let mut variants = Vec::new();
+        for discriminant in 0..=u8::MAX {
+            data[0] = discriminant;
+            let Ok(instruction) = GideonInstruction::deserialize(&mut &data[..]) else {
+                break;
+            };
+            let debug = format!("{:?}", instruction);
+            let name = debug.split(['(', ' ']).next().unwrap().to_string();
+            variants.push((name, instruction.pause_flag()));
+        }
+
+        let expected: Vec<_> = PAUSE_FLAGS
+            .iter()
+            .map(|(name, flag)| (name.to_string(), *flag))
+            .collect();
+        assert_eq!(variants, expected);
+    }
 }
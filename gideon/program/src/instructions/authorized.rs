@@ -0,0 +1,291 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    borsh1::try_from_slice_unchecked,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::instruction as token_instruction;
+
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    instructions::{burn::check_redeemable, receipt::create_receipt},
+    state::{
+        authority::MintAuthoritySigner, config::Config, escrow::Escrow, merchant::Merchant,
+        nonce::RedemptionNonce,
+    },
+    utils::{
+        check_token_program, close_account, create_pda_account, transfer_lamports,
+        verify_ed25519_signature,
+    },
+};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct RedeemWithAuthorizationArgs {
+    pub amount: u64,
+    pub nonce: u64,
+    pub deadline: i64,
+    /// SHA-256 of the GTIN or SKU the merchant supplied, required for product-bound vouchers
+    pub product: Option<[u8; 32]>,
+    /// Partner order or tracking reference for this redemption, defaulting to the voucher's
+    pub external_ref: Option<Vec<u8>>,
+}
+
+/// The message a voucher holder signs off-chain to let a merchant redeem one use of the voucher.
+///
+/// It binds the program, so a signature can't be replayed against another deployment, and
+/// the product and external reference the merchant will submit.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct RedemptionAuthorization {
+    pub program_id: Pubkey,
+    pub voucher_mint: Pubkey,
+    pub merchant: Pubkey,
+    pub amount: u64,
+    pub nonce: u64,
+    pub deadline: i64,
+    pub product: Option<[u8; 32]>,
+    pub external_ref: Option<Vec<u8>>,
+}
+
+impl RedemptionAuthorization {
+    pub const MESSAGE_PREFIX: &'static [u8] = b"gideon:redeem:";
+
+    /// Bytes the holder signs: a domain prefix followed by the borsh-encoded authorization.
+    pub fn to_message(&self) -> Vec<u8> {
+        let mut message = Self::MESSAGE_PREFIX.to_vec();
+        message.extend_from_slice(&borsh::to_vec(self).unwrap());
+        message
+    }
+}
+
+pub fn redeem_with_authorization(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: RedeemWithAuthorizationArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let merchant = next_account_info(accounts_iter)?;
+    let holder = next_account_info(accounts_iter)?;
+    let ata = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let nonce_account = next_account_info(accounts_iter)?;
+    let escrow_payer = next_account_info(accounts_iter)?;
+    let instructions_sysvar = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let receipt_account = next_account_info(accounts_iter)?;
+
+    if !merchant.is_signer {
+        msg!("Merchant must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let config = Config::load(program_id, config_account)?;
+    config.check_treasury(treasury)?;
+    check_token_program(token_program, &[mint_account, ata])?;
+    Escrow::check_external_ref(args.external_ref.as_deref())?;
+
+    // Check escrow recipient
+    let mut escrow = Escrow::load(program_id, escrow_account)?;
+    if escrow.recipient != *holder.key {
+        msg!("Invalid holder account");
+        return Err(GideonError::InvalidRecipientAccount.into());
+    }
+
+    // Check escrow payer, who gets the rent deposit back
+    if escrow.payer != *escrow_payer.key {
+        msg!("Invalid escrow payer account");
+        return Err(GideonError::InvalidIssuer.into());
+    }
+
+    // Check mint authority, which burns the voucher as its permanent delegate
+    let authority = MintAuthoritySigner::load(program_id, mint_authority, &escrow.issuer, true)?;
+
+    check_redeemable(
+        &escrow,
+        escrow_account,
+        mint_account,
+        ata,
+        Some(holder.key),
+        args.product.as_ref(),
+    )?;
+
+    // Product attestations must come from a merchant the issuer registered
+    if args.product.is_some() {
+        let merchant_registration = next_account_info(accounts_iter)?;
+        Merchant::check_registered(
+            program_id,
+            merchant_registration,
+            &escrow.issuer,
+            merchant.key,
+        )?;
+    }
+
+    // The holder authorizes a lamport amount, which a fiat voucher only fixes at settlement
+    if escrow.fiat.is_some() {
+        msg!("Fiat vouchers are redeemed by their holder");
+        return Err(GideonError::FiatRedemptionUnsupported.into());
+    }
+
+    // Check the authorization itself
+    if Clock::get()?.unix_timestamp > args.deadline {
+        msg!("Authorization expired");
+        return Err(GideonError::AuthorizationExpired.into());
+    }
+    let amount = escrow.per_redemption_amount.min(escrow.amount);
+    if args.amount != amount {
+        msg!(
+            "Authorization must be for exactly one use of {} lamports",
+            amount
+        );
+        return Err(GideonError::InvalidRedemptionAmount.into());
+    }
+    let authorization = RedemptionAuthorization {
+        program_id: *program_id,
+        voucher_mint: *mint_account.key,
+        merchant: *merchant.key,
+        amount: args.amount,
+        nonce: args.nonce,
+        deadline: args.deadline,
+        product: args.product,
+        external_ref: args.external_ref.clone(),
+    };
+    verify_ed25519_signature(instructions_sysvar, holder.key, &authorization.to_message())?;
+
+    // Check and advance the replay-protection nonce
+    let (nonce_pda, nonce_bump) = Pubkey::find_program_address(
+        &[
+            RedemptionNonce::SEED_PREFIX.as_bytes(),
+            mint_account.key.as_ref(),
+        ],
+        program_id,
+    );
+    if nonce_account.key != &nonce_pda {
+        msg!("Invalid nonce account");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let mut nonce = if nonce_account.data_is_empty() {
+        msg!("Creating redemption nonce account");
+        create_pda_account(
+            merchant,
+            nonce_account,
+            system_program,
+            RedemptionNonce::ACCOUNT_SPACE,
+            program_id,
+            &[
+                RedemptionNonce::SEED_PREFIX.as_bytes(),
+                mint_account.key.as_ref(),
+                &[nonce_bump],
+            ],
+        )?;
+        RedemptionNonce {
+            voucher_mint: *mint_account.key,
+            last_nonce: 0,
+            bump: nonce_bump,
+        }
+    } else {
+        try_from_slice_unchecked::<RedemptionNonce>(&nonce_account.data.borrow())?
+    };
+    if args.nonce <= nonce.last_nonce {
+        msg!("Nonce already used");
+        return Err(GideonError::NonceAlreadyUsed.into());
+    }
+    nonce.last_nonce = args.nonce;
+    nonce.serialize(&mut &mut nonce_account.data.borrow_mut()[..])?;
+
+    let redemption = escrow.next_redemption();
+    let external_ref = args.external_ref.or_else(|| escrow.external_ref.clone());
+    escrow.amount -= amount;
+    escrow.redemptions_remaining = escrow.redemptions_remaining.saturating_sub(1);
+    escrow.transition(escrow.redeemed_status(), Clock::get()?.unix_timestamp)?;
+
+    // Burn the voucher on its last use, through the program's permanent delegate
+    if escrow.redemptions_remaining == 0 {
+        invoke_signed(
+            &token_instruction::burn_checked(
+                token_program.key,
+                ata.key,
+                mint_account.key,
+                mint_authority.key,
+                &[mint_authority.key],
+                1,
+                0,
+            )?,
+            &[
+                ata.clone(),
+                mint_account.clone(),
+                mint_authority.clone(),
+                token_program.clone(),
+            ],
+            &[&authority.seeds()],
+        )?;
+    }
+
+    // Pay the redeemed value, less the protocol fee, to the merchant
+    let fee = config.redemption_fee(amount);
+    transfer_lamports(escrow_account, treasury, fee)?;
+    transfer_lamports(escrow_account, merchant, amount - fee)?;
+    create_receipt(
+        program_id,
+        receipt_account,
+        merchant,
+        system_program,
+        &escrow,
+        holder.key,
+        merchant.key,
+        amount,
+        redemption,
+        args.product,
+        external_ref.as_deref(),
+    )?;
+
+    GideonEvent::VoucherRedeemedByAuthorization {
+        voucher_mint: *mint_account.key,
+        escrow: *escrow_account.key,
+        holder: *holder.key,
+        merchant: *merchant.key,
+        amount,
+        fee,
+        nonce: args.nonce,
+        redemptions_remaining: escrow.redemptions_remaining,
+    }
+    .emit();
+    GideonEvent::emit_external_ref(*mint_account.key, *escrow_account.key, external_ref);
+
+    if let Some(product) = args.product {
+        GideonEvent::ProductRedeemed {
+            voucher_mint: *mint_account.key,
+            escrow: *escrow_account.key,
+            issuer: escrow.issuer,
+            merchant: *merchant.key,
+            product,
+            amount,
+        }
+        .emit();
+    }
+
+    if escrow.redemptions_remaining > 0 {
+        msg!("Redemptions remaining: {}", escrow.redemptions_remaining);
+        escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+        return Ok(());
+    }
+
+    // The voucher is gone, so its nonce can't be replayed and its rent goes back to the merchant
+    close_account(nonce_account, merchant)?;
+
+    // Return the rent deposit to the escrow payer and close escrow account
+    close_account(escrow_account, escrow_payer)?;
+
+    Ok(())
+}
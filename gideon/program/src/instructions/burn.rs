@@ -1,3 +1,4 @@
+use borsh::BorshSerialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     borsh1::try_from_slice_unchecked,
@@ -5,22 +6,41 @@ use solana_program::{
     entrypoint::ProgramResult,
     msg,
     program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_option::COption,
     pubkey::Pubkey,
     sysvar::Sysvar,
 };
 use spl_token_2022::{
     extension::{BaseStateWithExtensions, StateWithExtensions},
     instruction as token_instruction,
-    state::Mint,
+    state::{Account, Mint},
 };
 use spl_token_metadata_interface::state::TokenMetadata;
 
 use crate::{
     error::GideonError,
-    state::{authority::MintAuthorityPda, escrow::Escrow},
+    events::GideonEvent,
+    instructions::{
+        mint::{encode_product, MintVoucherArgs},
+        receipt::create_receipt,
+    },
+    oracle::Price,
+    state::{authority::MintAuthoritySigner, config::Config, escrow::Escrow},
+    utils::{check_token_program, close_account, transfer_lamports},
 };
 
+/// Redeems every remaining use of the voucher at once, then burns it.
 pub fn burn_voucher_release_escrow(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    redeem(program_id, accounts, false)
+}
+
+/// Redeems a single use of the voucher, burning it only on the last use.
+pub fn redeem_voucher(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    redeem(program_id, accounts, true)
+}
+
+fn redeem(program_id: &Pubkey, accounts: &[AccountInfo], single_use: bool) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
     let payer = next_account_info(accounts_iter)?;
@@ -29,35 +49,240 @@ pub fn burn_voucher_release_escrow(program_id: &Pubkey, accounts: &[AccountInfo]
     let mint_authority = next_account_info(accounts_iter)?;
     let escrow_account = next_account_info(accounts_iter)?;
     let token_program = next_account_info(accounts_iter)?;
-    let clock_program = next_account_info(accounts_iter)?;
-    let _ = next_account_info(accounts_iter)?;
+    let _clock_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let escrow_payer = next_account_info(accounts_iter)?;
+    let payout = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let receipt_account = next_account_info(accounts_iter)?;
 
-    let (mint_authority_pda, bump) =
-        Pubkey::find_program_address(&[MintAuthorityPda::SEED_PREFIX.as_bytes()], program_id);
+    if !payer.is_signer {
+        msg!("Payer must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let config = Config::load(program_id, config_account)?;
+    config.check_treasury(treasury)?;
+    check_token_program(token_program, &[mint_account, ata])?;
 
+    // Check escrow recipient, or a delegate the recipient approved on their voucher account
+    let mut escrow = Escrow::load(program_id, escrow_account)?;
+    let is_delegate = escrow.recipient != *payer.key;
+    if is_delegate {
+        let ata_data = ata.try_borrow_data()?;
+        let token_account = StateWithExtensions::<Account>::unpack(&ata_data)?;
+        if token_account.base.delegate != COption::Some(*payer.key)
+            || token_account.base.delegated_amount < 1
+        {
+            msg!("Invalid payer account");
+            return Err(GideonError::InvalidRecipientAccount.into());
+        }
+    }
+
+    // Check payout account, the recipient unless they chose another address
+    if *payout.key != escrow.payout_address.unwrap_or(escrow.recipient) {
+        msg!("Invalid payout account");
+        return Err(GideonError::InvalidPayoutAccount.into());
+    }
+
+    // Check escrow payer, who gets the rent deposit back
+    if escrow.payer != *escrow_payer.key {
+        msg!("Invalid escrow payer account");
+        return Err(GideonError::InvalidIssuer.into());
+    }
     // Check mint authority
-    if mint_authority.key != &mint_authority_pda {
-        msg!("Invalid mint authority account");
-        return Err(GideonError::InvalidMintAuthority.into());
+    let authority = MintAuthoritySigner::load(program_id, mint_authority, &escrow.issuer, true)?;
+
+    check_redeemable(
+        &escrow,
+        escrow_account,
+        mint_account,
+        ata,
+        Some(&escrow.recipient),
+        None,
+    )?;
+
+    // Work out how much this redemption pays out
+    let now = Clock::get()?.unix_timestamp;
+    let redemption = escrow.next_redemption();
+    let amount = if let Some(fiat) = &escrow.fiat {
+        // Pay the face value at the oracle price, the excess collateral goes back to the
+        // escrow payer when the escrow is closed
+        let price_feed = next_account_info(accounts_iter)?;
+        if *price_feed.key != fiat.price_feed {
+            msg!("Invalid price feed account");
+            return Err(GideonError::InvalidPriceFeed.into());
+        }
+        let price = Price::load(price_feed, &config.oracle_program, now)?;
+        let amount = price
+            .lamports_for(fiat.face_value, fiat.decimals)?
+            .min(escrow.amount);
+
+        GideonEvent::FiatRedemptionSettled {
+            voucher_mint: *mint_account.key,
+            escrow: *escrow_account.key,
+            currency: fiat.currency,
+            face_value: fiat.face_value,
+            price: price.price,
+            expo: price.expo,
+            amount,
+            excess: escrow.amount - amount,
+        }
+        .emit();
+
+        amount
+    } else if single_use {
+        escrow.per_redemption_amount.min(escrow.amount)
+    } else {
+        escrow.amount
+    };
+    escrow.amount -= amount;
+    escrow.redemptions_remaining = if single_use {
+        escrow.redemptions_remaining.saturating_sub(1)
+    } else {
+        0
+    };
+    escrow.transition(escrow.redeemed_status(), now)?;
+
+    // Burn the voucher on its last use, as the holder or their delegate
+    if escrow.redemptions_remaining == 0 {
+        invoke_signed(
+            &token_instruction::burn_checked(
+                token_program.key,
+                ata.key,
+                mint_account.key,
+                payer.key,
+                &[payer.key],
+                1,
+                0,
+            )?,
+            &[
+                ata.clone(),
+                mint_account.clone(),
+                mint_authority.clone(),
+                payer.clone(),
+            ],
+            &[&authority.seeds()],
+        )?;
+
+        // Close ATA account, which only the holder can do
+        if !is_delegate && ata.lamports() > 0 {
+            invoke(
+                &token_instruction::close_account(
+                    token_program.key,
+                    ata.key,
+                    payer.key,
+                    payer.key,
+                    &[payer.key],
+                )?,
+                &[ata.clone(), payer.clone(), token_program.clone()],
+            )?;
+        }
+    }
+
+    // Pay the redeemed value, less the protocol fee, to the recipient's payout account
+    let fee = config.redemption_fee(amount);
+    transfer_lamports(escrow_account, treasury, fee)?;
+    transfer_lamports(escrow_account, payout, amount - fee)?;
+    create_receipt(
+        program_id,
+        receipt_account,
+        payer,
+        system_program,
+        &escrow,
+        &escrow.recipient,
+        payout.key,
+        amount,
+        redemption,
+        None,
+        escrow.external_ref.as_deref(),
+    )?;
+
+    GideonEvent::VoucherRedeemed {
+        voucher_mint: *mint_account.key,
+        escrow: *escrow_account.key,
+        recipient: *payout.key,
+        amount,
+        fee,
+        redemptions_remaining: escrow.redemptions_remaining,
+    }
+    .emit();
+    GideonEvent::emit_external_ref(
+        *mint_account.key,
+        *escrow_account.key,
+        escrow.external_ref.clone(),
+    );
+
+    if escrow.redemptions_remaining > 0 {
+        msg!("Redemptions remaining: {}", escrow.redemptions_remaining);
+        escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+        return Ok(());
+    }
+
+    // Return the rent deposit to the escrow payer and close escrow account
+    close_account(escrow_account, escrow_payer)?;
+
+    Ok(())
+}
+
+/// Checks that the voucher linked to `escrow_account` can be redeemed right now from `ata`.
+///
+/// When `holder` is `None` the voucher may be held by anyone, e.g. for code-based redemptions.
+/// Product-bound vouchers need the `product` a merchant attests to having supplied.
+pub(crate) fn check_redeemable(
+    escrow: &Escrow,
+    escrow_account: &AccountInfo,
+    mint_account: &AccountInfo,
+    ata: &AccountInfo,
+    holder: Option<&Pubkey>,
+    product: Option<&[u8; 32]>,
+) -> ProgramResult {
+    escrow.check_not_disputed()?;
+    if escrow.milestone_count > 0 {
+        msg!("Voucher released by milestones");
+        return Err(GideonError::VoucherReleasedByMilestones.into());
+    }
+    if escrow.confirmation_window > 0 {
+        msg!("Voucher requires delivery confirmation");
+        return Err(GideonError::DeliveryConfirmationRequired.into());
+    }
+
+    check_voucher(escrow, escrow_account, mint_account, ata, holder, product)
+}
+
+/// Checks the voucher linked to `escrow_account` is not frozen, is within its validity
+/// window, covers `product` if bound to products, and is held in `ata`, by `holder` if given.
+pub(crate) fn check_voucher(
+    escrow: &Escrow,
+    escrow_account: &AccountInfo,
+    mint_account: &AccountInfo,
+    ata: &AccountInfo,
+    holder: Option<&Pubkey>,
+    product: Option<&[u8; 32]>,
+) -> ProgramResult {
+    escrow.check_not_frozen()?;
+
+    // Check the voucher is the escrow's own mint, held in a Token-2022 account
+    if escrow.voucher_mint != *mint_account.key {
+        msg!("Invalid voucher mint account");
+        return Err(GideonError::InvalidVoucherEscrowAccount.into());
+    }
+    if *mint_account.owner != spl_token_2022::id() || *ata.owner != spl_token_2022::id() {
+        msg!("Voucher accounts not owned by the token program");
+        return Err(ProgramError::IllegalOwner);
     }
 
-    // Check mint authority bump
-    let mint_authority_data =
-        try_from_slice_unchecked::<MintAuthorityPda>(&mint_authority.data.borrow()).unwrap();
-    if mint_authority_data.bump != bump {
-        msg!("Invalid mint authority account");
-        return Err(GideonError::InvalidMintAuthority.into());
+    // Check voucher validity window
+    let now = Clock::get()?.unix_timestamp;
+    if now < escrow.valid_from {
+        msg!("Voucher not yet valid");
+        return Err(GideonError::VoucherNotYetValid.into());
     }
-    drop(mint_authority_data);
-
-    // Check escrow recipient
-    let escrow_data = escrow_account.data.borrow();
-    let escrow = try_from_slice_unchecked::<Escrow>(&escrow_data).unwrap();
-    if escrow.recipient != *payer.key {
-        msg!("Invalid payer account");
-        return Err(GideonError::InvalidRecipientAccount.into());
+    if now > escrow.settlement_deadline() {
+        msg!("Voucher expired");
+        return Err(GideonError::VoucherExpired.into());
     }
-    drop(escrow_data);
 
     // Get token metadata
     {
@@ -78,70 +303,40 @@ pub fn burn_voucher_release_escrow(program_id: &Pubkey, accounts: &[AccountInfo]
             return Err(GideonError::InvalidVoucherEscrowAccount.into());
         }
 
-        // Check voucher expiry
-        let voucher_expiry = metadata
+        // Check the attested product is one a product-bound voucher can buy
+        if let Some((_, products)) = metadata
             .additional_metadata
             .iter()
-            .find(|(key, _)| key == "expiry")
-            .map(|(_, value)| value.parse::<i64>().unwrap())
-            .unwrap()
-            / 1000;
-        let clock = Clock::from_account_info(clock_program)?;
-        if clock.unix_timestamp > voucher_expiry {
-            msg!("Voucher expired");
-            return Err(GideonError::VoucherExpired.into());
+            .find(|(key, _)| key == MintVoucherArgs::PRODUCTS_FIELD)
+        {
+            let Some(product) = product else {
+                msg!("Product attestation required");
+                return Err(GideonError::ProductAttestationRequired.into());
+            };
+            if !products
+                .split(',')
+                .any(|entry| entry == encode_product(product))
+            {
+                msg!("Product not covered by voucher");
+                return Err(GideonError::ProductNotAllowed.into());
+            }
         }
 
         drop(mint_data);
     }
 
     // Check voucher ownership
-    // TODO: Implement this
-
-    // Burn voucher
-    invoke_signed(
-        &token_instruction::burn_checked(
-            token_program.key,
-            ata.key,
-            mint_account.key,
-            payer.key,
-            &[payer.key],
-            1,
-            0,
-        )?,
-        &[
-            ata.clone(),
-            mint_account.clone(),
-            mint_authority.clone(),
-            payer.clone(),
-        ],
-        &[&[MintAuthorityPda::SEED_PREFIX.as_bytes(), &[bump]]],
-    )?;
-
-    // Close ATA account
-    if ata.lamports() > 0 {
-        invoke(
-            &token_instruction::close_account(
-                token_program.key,
-                ata.key,
-                payer.key,
-                payer.key,
-                &[payer.key],
-            )?,
-            &[ata.clone(), payer.clone(), token_program.clone()],
-        )?;
+    {
+        let ata_data = ata.try_borrow_data()?;
+        let token_account = StateWithExtensions::<Account>::unpack(&ata_data)?;
+        if token_account.base.mint != *mint_account.key
+            || holder.is_some_and(|holder| token_account.base.owner != *holder)
+            || token_account.base.amount != 1
+        {
+            msg!("Invalid voucher holder");
+            return Err(GideonError::InvalidVoucherHolder.into());
+        }
     }
 
-    // Transfer lamports from escrow to payer and close escrow account
-    let payer_lamports = payer.lamports();
-    **payer.lamports.borrow_mut() = payer_lamports
-        .checked_add(escrow_account.lamports())
-        .unwrap();
-    **escrow_account.lamports.borrow_mut() = 0;
-
-    // Clear escrow account data
-    let mut escrow_data = escrow_account.data.borrow_mut();
-    escrow_data.fill(0);
-
     Ok(())
 }
@@ -0,0 +1,339 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    instructions::{
+        escrow::create_escrow,
+        mint::{mint_voucher, MintVoucherArgs},
+    },
+    merkle,
+    state::{campaign::Campaign, config::Config, escrow::Escrow, issuer::Issuer},
+    utils::{close_account, create_pda_account, transfer_lamports},
+};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct CreateCampaignArgs {
+    pub campaign_id: u64,
+    pub merkle_root: [u8; 32],
+    pub leaf_count: u32,
+    /// Sum of the amounts of every leaf
+    pub total_amount: u64,
+    pub claim_until: i64,
+    pub grace_period: i64,
+    pub title: String,
+    pub description: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct ClaimVoucherArgs {
+    pub index: u32,
+    pub amount: u64,
+    pub expiry: i64,
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// Creates an airdrop campaign, funding its vault with the value of every voucher in the
+/// tree and the rent for their escrows.
+pub fn create_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: CreateCampaignArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let campaign_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let issuer_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    let config = Config::load(program_id, config_account)?;
+    config.check_treasury(treasury)?;
+
+    // Check the authority may issue vouchers for the issuer
+    let issuer = Issuer::load(program_id, issuer_account)?;
+    if !authority.is_signer || !issuer.is_signer(authority.key) {
+        msg!("Unauthorized issuer signer");
+        return Err(GideonError::UnauthorizedIssuerSigner.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    if args.leaf_count == 0
+        || args.leaf_count > Campaign::MAX_LEAVES
+        || args.total_amount == 0
+        || args.claim_until <= now
+        || args.claim_until.saturating_sub(now) > config.max_validity_period
+        || !(0..=config.max_grace_period).contains(&args.grace_period)
+        || args.title.len() > Campaign::MAX_TITLE_LEN
+        || args.description.len() > Campaign::MAX_DESCRIPTION_LEN
+        || args.symbol.len() > Campaign::MAX_SYMBOL_LEN
+        || args.uri.len() > Campaign::MAX_URI_LEN
+    {
+        msg!("Invalid campaign");
+        return Err(GideonError::InvalidCampaign.into());
+    }
+
+    let (campaign_pda, campaign_bump) = Pubkey::find_program_address(
+        &[
+            Campaign::SEED_PREFIX.as_bytes(),
+            issuer_account.key.as_ref(),
+            &args.campaign_id.to_le_bytes(),
+        ],
+        program_id,
+    );
+    if campaign_account.key != &campaign_pda {
+        msg!("Invalid campaign account");
+        return Err(GideonError::InvalidCampaign.into());
+    }
+
+    let rent = Rent::get()?;
+    let escrow_rent = rent.minimum_balance(Escrow::ACCOUNT_SPACE);
+    let campaign = Campaign {
+        issuer: *issuer_account.key,
+        authority: *authority.key,
+        campaign_id: args.campaign_id,
+        merkle_root: args.merkle_root,
+        leaf_count: args.leaf_count,
+        total_amount: args.total_amount,
+        claimed_amount: 0,
+        claim_until: args.claim_until,
+        grace_period: args.grace_period,
+        escrow_rent,
+        title: args.title,
+        description: args.description,
+        symbol: args.symbol,
+        uri: args.uri,
+        claimed: vec![0; (args.leaf_count as usize).div_ceil(8)],
+        bump: campaign_bump,
+    };
+
+    // The vault holds the voucher value and escrow rent on top of its own rent
+    let space = borsh::to_vec(&campaign)?.len();
+    let vault_amount = escrow_rent
+        .checked_mul(args.leaf_count as u64)
+        .and_then(|escrow_rents| escrow_rents.checked_add(args.total_amount))
+        .and_then(|funding| funding.checked_add(rent.minimum_balance(space)))
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    msg!("Creating campaign account");
+    create_pda_account(
+        authority,
+        campaign_account,
+        system_program,
+        space,
+        program_id,
+        &[
+            Campaign::SEED_PREFIX.as_bytes(),
+            issuer_account.key.as_ref(),
+            &args.campaign_id.to_le_bytes(),
+            &[campaign_bump],
+        ],
+    )?;
+    let funding = vault_amount.saturating_sub(campaign_account.lamports());
+    if funding > 0 {
+        msg!("Funding campaign vault");
+        invoke(
+            &system_instruction::transfer(authority.key, campaign_account.key, funding),
+            &[
+                authority.clone(),
+                campaign_account.clone(),
+                system_program.clone(),
+            ],
+        )?;
+    }
+    campaign.serialize(&mut &mut campaign_account.data.borrow_mut()[..])?;
+
+    // Charge the issuance fee for every voucher in the campaign up front
+    let fee = config.batch_issuance_fee(args.total_amount, args.leaf_count as u64);
+    if fee > 0 {
+        msg!("Transferring issuance fee to treasury");
+        invoke(
+            &system_instruction::transfer(authority.key, treasury.key, fee),
+            &[authority.clone(), treasury.clone(), system_program.clone()],
+        )?;
+    }
+
+    GideonEvent::CampaignCreated {
+        campaign: *campaign_account.key,
+        issuer: *issuer_account.key,
+        merkle_root: campaign.merkle_root,
+        leaf_count: campaign.leaf_count,
+        total_amount: campaign.total_amount,
+        fee,
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Claims a voucher from a campaign, creating its escrow from the vault and minting the
+/// voucher NFT to the claiming recipient.
+pub fn claim_voucher(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: ClaimVoucherArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let escrow_account = next_account_info(accounts_iter)?;
+    let recipient = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let _mint_authority = next_account_info(accounts_iter)?;
+    let _associated_token_account = next_account_info(accounts_iter)?;
+    let _rent = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let _token_program = next_account_info(accounts_iter)?;
+    let _associated_token_program = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let campaign_account = next_account_info(accounts_iter)?;
+
+    if !recipient.is_signer {
+        msg!("Recipient must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let config = Config::load(program_id, config_account)?;
+    let mut campaign = Campaign::load(program_id, campaign_account)?;
+
+    // Check the claim against the campaign and its tree
+    let now = Clock::get()?.unix_timestamp;
+    if now > campaign.claim_until {
+        msg!("Campaign claim period over");
+        return Err(GideonError::CampaignClosed.into());
+    }
+    if args.index >= campaign.leaf_count || campaign.is_claimed(args.index) {
+        msg!("Voucher already claimed");
+        return Err(GideonError::VoucherAlreadyClaimed.into());
+    }
+    let leaf = merkle::leaf_hash(args.index, recipient.key, args.amount, args.expiry);
+    if !merkle::verify(&args.proof, &campaign.merkle_root, leaf) {
+        msg!("Invalid merkle proof");
+        return Err(GideonError::InvalidMerkleProof.into());
+    }
+
+    // The vault only ever pays out what the campaign was funded with
+    let claimed_amount = campaign
+        .claimed_amount
+        .checked_add(args.amount)
+        .filter(|claimed_amount| *claimed_amount <= campaign.total_amount)
+        .ok_or(GideonError::InvalidCampaign)?;
+
+    // Check the voucher itself against the platform limits
+    if args.amount > config.max_voucher_amount {
+        msg!("Voucher amount above limit");
+        return Err(GideonError::VoucherAmountTooLarge.into());
+    }
+    if args.expiry <= now || args.expiry.saturating_sub(now) > config.max_validity_period {
+        msg!("Invalid validity window");
+        return Err(GideonError::InvalidValidityWindow.into());
+    }
+
+    // The escrow belongs to the campaign authority, who reclaims it if it expires unused
+    create_escrow(
+        program_id,
+        escrow_account,
+        recipient,
+        system_program,
+        Escrow::new(
+            campaign.authority,
+            *recipient.key,
+            args.amount,
+            0,
+            *mint_account.key,
+            now,
+            args.expiry,
+            campaign.grace_period,
+            1,
+            args.amount,
+            None,
+            campaign.issuer,
+            None,
+            0,
+            0,
+        ),
+    )?;
+
+    // Fund the escrow from the vault and refund the recipient the escrow rent
+    msg!("Funding escrow from campaign vault");
+    transfer_lamports(campaign_account, escrow_account, args.amount)?;
+    transfer_lamports(campaign_account, recipient, campaign.escrow_rent)?;
+
+    campaign.set_claimed(args.index);
+    campaign.claimed_amount = claimed_amount;
+    campaign.serialize(&mut &mut campaign_account.data.borrow_mut()[..])?;
+
+    GideonEvent::VoucherClaimed {
+        campaign: *campaign_account.key,
+        index: args.index,
+        voucher_mint: *mint_account.key,
+        escrow: *escrow_account.key,
+        recipient: *recipient.key,
+        amount: args.amount,
+    }
+    .emit();
+
+    mint_voucher(
+        program_id,
+        accounts,
+        MintVoucherArgs {
+            title: campaign.title,
+            description: campaign.description,
+            symbol: campaign.symbol,
+            uri: campaign.uri,
+            products: Vec::new(),
+        },
+    )
+}
+
+/// Returns everything left in a campaign's vault to its authority once claims have closed.
+pub fn close_campaign(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let authority = next_account_info(accounts_iter)?;
+    let campaign_account = next_account_info(accounts_iter)?;
+    let _config = next_account_info(accounts_iter)?;
+
+    if !authority.is_signer {
+        msg!("Authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let campaign = Campaign::load(program_id, campaign_account)?;
+    if campaign.authority != *authority.key {
+        msg!("Invalid campaign authority");
+        return Err(GideonError::InvalidCampaign.into());
+    }
+    if Clock::get()?.unix_timestamp <= campaign.claim_until {
+        msg!("Campaign claim period not over");
+        return Err(GideonError::CampaignStillOpen.into());
+    }
+
+    GideonEvent::CampaignClosed {
+        campaign: *campaign_account.key,
+        unclaimed_amount: campaign
+            .total_amount
+            .saturating_sub(campaign.claimed_amount),
+    }
+    .emit();
+
+    msg!("Closing campaign");
+    close_account(campaign_account, authority)?;
+
+    Ok(())
+}
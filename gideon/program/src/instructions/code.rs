@@ -0,0 +1,198 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    hash::hashv,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::{
+    extension::StateWithExtensions, instruction as token_instruction, state::Account,
+};
+
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    instructions::{burn::check_redeemable, receipt::create_receipt},
+    state::{
+        authority::MintAuthoritySigner, config::Config, escrow::Escrow, merchant::Merchant,
+        status::VoucherStatus,
+    },
+    utils::{check_token_program, close_account, transfer_lamports},
+};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct RedeemWithCodeArgs {
+    pub preimage: Vec<u8>,
+    /// SHA-256 of the GTIN or SKU the merchant supplied, required for product-bound vouchers
+    pub product: Option<[u8; 32]>,
+    /// Partner order or tracking reference for this redemption, defaulting to the voucher's
+    pub external_ref: Option<Vec<u8>>,
+}
+
+impl RedeemWithCodeArgs {
+    /// Shortest code accepted, so a random code can't be brute-forced from its public hash
+    pub const MIN_PREIMAGE_LEN: usize = 16;
+    pub const MAX_PREIMAGE_LEN: usize = 64;
+}
+
+/// Hash stored on the escrow for a redemption code, bound to the voucher it unlocks.
+///
+/// The hash is public, so codes must be high-entropy random bytes, e.g. from a CSPRNG, never
+/// words or PINs. Codes shorter than `MIN_PREIMAGE_LEN` or longer than `MAX_PREIMAGE_LEN`
+/// bytes are rejected, both when hashing a code for issuance and when redeeming one.
+pub fn redemption_code_hash(
+    voucher_mint: &Pubkey,
+    preimage: &[u8],
+) -> Result<[u8; 32], ProgramError> {
+    let accepted_len = RedeemWithCodeArgs::MIN_PREIMAGE_LEN..=RedeemWithCodeArgs::MAX_PREIMAGE_LEN;
+    if !accepted_len.contains(&preimage.len()) {
+        msg!("Invalid redemption code length");
+        return Err(GideonError::InvalidRedemptionCode.into());
+    }
+
+    Ok(hashv(&[voucher_mint.as_ref(), preimage]).to_bytes())
+}
+
+/// Redeems a voucher for whoever presents its secret code, paying the merchant who submits it.
+///
+/// The code is public once submitted, so all remaining uses are redeemed at once and the
+/// voucher is burned from whichever account holds it. Only merchants registered with the
+/// voucher's issuer can submit codes, so a code seen in a pending transaction can't be
+/// front-run by an arbitrary account to take the payout.
+pub fn redeem_with_code(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: RedeemWithCodeArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let merchant = next_account_info(accounts_iter)?;
+    let ata = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let escrow_payer = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let receipt_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let merchant_registration = next_account_info(accounts_iter)?;
+
+    if !merchant.is_signer {
+        msg!("Merchant must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let config = Config::load(program_id, config_account)?;
+    config.check_treasury(treasury)?;
+    check_token_program(token_program, &[mint_account, ata])?;
+    Escrow::check_external_ref(args.external_ref.as_deref())?;
+
+    let mut escrow = Escrow::load(program_id, escrow_account)?;
+    Merchant::check_registered(
+        program_id,
+        merchant_registration,
+        &escrow.issuer,
+        merchant.key,
+    )?;
+
+    // Check escrow payer, who gets the rent deposit back
+    if escrow.payer != *escrow_payer.key {
+        msg!("Invalid escrow payer account");
+        return Err(GideonError::InvalidIssuer.into());
+    }
+
+    // Check redemption code
+    if escrow.code_hash != Some(redemption_code_hash(mint_account.key, &args.preimage)?) {
+        msg!("Invalid redemption code");
+        return Err(GideonError::InvalidRedemptionCode.into());
+    }
+
+    // Check mint authority, which burns the voucher as its permanent delegate
+    let authority = MintAuthoritySigner::load(program_id, mint_authority, &escrow.issuer, true)?;
+
+    check_redeemable(
+        &escrow,
+        escrow_account,
+        mint_account,
+        ata,
+        None,
+        args.product.as_ref(),
+    )?;
+    escrow.transition(VoucherStatus::Redeemed, Clock::get()?.unix_timestamp)?;
+    let holder = StateWithExtensions::<Account>::unpack(&ata.try_borrow_data()?)?
+        .base
+        .owner;
+    let external_ref = args.external_ref.or_else(|| escrow.external_ref.clone());
+
+    // Burn voucher through the program's permanent delegate
+    invoke_signed(
+        &token_instruction::burn_checked(
+            token_program.key,
+            ata.key,
+            mint_account.key,
+            mint_authority.key,
+            &[mint_authority.key],
+            1,
+            0,
+        )?,
+        &[
+            ata.clone(),
+            mint_account.clone(),
+            mint_authority.clone(),
+            token_program.clone(),
+        ],
+        &[&authority.seeds()],
+    )?;
+
+    // Pay the remaining value, less the protocol fee, to the merchant
+    let fee = config.redemption_fee(escrow.amount);
+    transfer_lamports(escrow_account, treasury, fee)?;
+    transfer_lamports(escrow_account, merchant, escrow.amount - fee)?;
+    create_receipt(
+        program_id,
+        receipt_account,
+        merchant,
+        system_program,
+        &escrow,
+        &holder,
+        merchant.key,
+        escrow.amount,
+        escrow.next_redemption(),
+        args.product,
+        external_ref.as_deref(),
+    )?;
+
+    GideonEvent::VoucherRedeemedWithCode {
+        voucher_mint: *mint_account.key,
+        escrow: *escrow_account.key,
+        merchant: *merchant.key,
+        amount: escrow.amount,
+        fee,
+    }
+    .emit();
+    GideonEvent::emit_external_ref(*mint_account.key, *escrow_account.key, external_ref);
+
+    if let Some(product) = args.product {
+        GideonEvent::ProductRedeemed {
+            voucher_mint: *mint_account.key,
+            escrow: *escrow_account.key,
+            issuer: escrow.issuer,
+            merchant: *merchant.key,
+            product,
+            amount: escrow.amount,
+        }
+        .emit();
+    }
+
+    // Return the rent deposit to the escrow payer and close escrow account
+    close_account(escrow_account, escrow_payer)?;
+
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    state::{config::Config, escrow::Escrow},
+    utils::create_pda_account,
+};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct InitConfigArgs {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub issuance_fee_bps: u16,
+    pub redemption_fee_bps: u16,
+    pub issuance_flat_fee: u64,
+    pub redemption_flat_fee: u64,
+    pub max_voucher_amount: u64,
+    pub max_validity_period: i64,
+    pub max_grace_period: i64,
+}
+
+/// Settings to change, `None` leaves a setting as it is.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct UpdateConfigArgs {
+    pub treasury: Option<Pubkey>,
+    pub issuance_fee_bps: Option<u16>,
+    pub redemption_fee_bps: Option<u16>,
+    pub issuance_flat_fee: Option<u64>,
+    pub redemption_flat_fee: Option<u64>,
+    pub max_voucher_amount: Option<u64>,
+    pub max_validity_period: Option<i64>,
+    pub max_grace_period: Option<i64>,
+    pub guardian: Option<Pubkey>,
+    pub recovery_requires_admin: Option<bool>,
+    pub oracle_program: Option<Pubkey>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct TransferAdminArgs {
+    pub new_admin: Pubkey,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct SetPauseArgs {
+    pub paused: u8,
+}
+
+/// Creates the config PDA. Only the program's upgrade authority may do this.
+pub fn init_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: InitConfigArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?;
+    let upgrade_authority = next_account_info(accounts_iter)?;
+    let program_data = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !upgrade_authority.is_signer {
+        msg!("Upgrade authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check upgrade authority against the program data account
+    let (program_data_address, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::ID);
+    if program_data.key != &program_data_address
+        || read_upgrade_authority(&program_data.data.borrow()) != Some(*upgrade_authority.key)
+    {
+        msg!("Invalid upgrade authority");
+        return Err(GideonError::InvalidUpgradeAuthority.into());
+    }
+
+    let (config_pda, bump) =
+        Pubkey::find_program_address(&[Config::SEED_PREFIX.as_bytes()], program_id);
+    if config_account.key != &config_pda {
+        msg!("Invalid config account");
+        return Err(GideonError::InvalidConfigAccount.into());
+    }
+
+    let config = Config {
+        admin: args.admin,
+        pending_admin: None,
+        treasury: args.treasury,
+        issuance_fee_bps: args.issuance_fee_bps,
+        redemption_fee_bps: args.redemption_fee_bps,
+        issuance_flat_fee: args.issuance_flat_fee,
+        redemption_flat_fee: args.redemption_flat_fee,
+        paused: 0,
+        max_voucher_amount: args.max_voucher_amount,
+        max_validity_period: args.max_validity_period,
+        max_grace_period: args.max_grace_period,
+        bump,
+        guardian: Pubkey::default(),
+        recovery_requires_admin: true,
+        oracle_program: Pubkey::default(),
+        reserved: [0; 31],
+    };
+    check_settings(&config)?;
+
+    msg!("Creating config PDA...");
+    create_pda_account(
+        upgrade_authority,
+        config_account,
+        system_program,
+        Config::ACCOUNT_SPACE,
+        program_id,
+        &[Config::SEED_PREFIX.as_bytes(), &[bump]],
+    )?;
+
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+pub fn update_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: UpdateConfigArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+
+    let mut config = Config::load(program_id, config_account)?;
+    check_admin(&config, admin)?;
+
+    if let Some(treasury) = args.treasury {
+        config.treasury = treasury;
+    }
+    if let Some(issuance_fee_bps) = args.issuance_fee_bps {
+        config.issuance_fee_bps = issuance_fee_bps;
+    }
+    if let Some(redemption_fee_bps) = args.redemption_fee_bps {
+        config.redemption_fee_bps = redemption_fee_bps;
+    }
+    if let Some(issuance_flat_fee) = args.issuance_flat_fee {
+        config.issuance_flat_fee = issuance_flat_fee;
+    }
+    if let Some(redemption_flat_fee) = args.redemption_flat_fee {
+        config.redemption_flat_fee = redemption_flat_fee;
+    }
+    if let Some(max_voucher_amount) = args.max_voucher_amount {
+        config.max_voucher_amount = max_voucher_amount;
+    }
+    if let Some(max_validity_period) = args.max_validity_period {
+        config.max_validity_period = max_validity_period;
+    }
+    if let Some(max_grace_period) = args.max_grace_period {
+        config.max_grace_period = max_grace_period;
+    }
+    if let Some(guardian) = args.guardian {
+        config.guardian = guardian;
+    }
+    if let Some(recovery_requires_admin) = args.recovery_requires_admin {
+        config.recovery_requires_admin = recovery_requires_admin;
+    }
+    if let Some(oracle_program) = args.oracle_program {
+        config.oracle_program = oracle_program;
+    }
+    check_settings(&config)?;
+
+    msg!("Updating config");
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Proposes a new admin, who takes over once they call `AcceptAdmin`.
+pub fn transfer_admin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: TransferAdminArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+
+    let mut config = Config::load(program_id, config_account)?;
+    check_admin(&config, admin)?;
+
+    msg!("Proposing new admin: {}", args.new_admin);
+    config.pending_admin = Some(args.new_admin);
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+pub fn accept_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?;
+    let new_admin = next_account_info(accounts_iter)?;
+
+    let mut config = Config::load(program_id, config_account)?;
+    if !new_admin.is_signer || config.pending_admin != Some(*new_admin.key) {
+        msg!("Invalid pending admin");
+        return Err(GideonError::InvalidAdmin.into());
+    }
+
+    msg!("New admin: {}", new_admin.key);
+    config.admin = *new_admin.key;
+    config.pending_admin = None;
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Sets which instruction groups are paused. The guardian can only pause more groups,
+/// while the admin can also unpause.
+pub fn set_pause(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: SetPauseArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+
+    let mut config = Config::load(program_id, config_account)?;
+    if !authority.is_signer {
+        msg!("Pause authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let is_admin = config.admin == *authority.key;
+    let is_guardian = config.guardian != Pubkey::default() && config.guardian == *authority.key;
+    // The guardian can only add pause flags, never clear them
+    let only_adds_flags = args.paused & config.paused == config.paused;
+    if !(is_admin || is_guardian && only_adds_flags) {
+        msg!("Invalid pause authority");
+        return Err(GideonError::InvalidAdmin.into());
+    }
+    if args.paused & !Config::PAUSE_ALL != 0 {
+        msg!("Unknown pause flags");
+        return Err(GideonError::InvalidConfigSetting.into());
+    }
+
+    msg!("Setting paused flags: {:#010b}", args.paused);
+    config.paused = args.paused;
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+    GideonEvent::PauseUpdated {
+        authority: *authority.key,
+        paused: args.paused,
+        timestamp: Clock::get()?.unix_timestamp,
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Checks that `admin` is the config admin and signed the transaction.
+pub(crate) fn check_admin(config: &Config, admin: &AccountInfo) -> ProgramResult {
+    if !admin.is_signer || config.admin != *admin.key {
+        msg!("Invalid admin");
+        return Err(GideonError::InvalidAdmin.into());
+    }
+
+    Ok(())
+}
+
+fn check_settings(config: &Config) -> ProgramResult {
+    if config.issuance_fee_bps > Config::MAX_FEE_BPS
+        || config.redemption_fee_bps > Config::MAX_FEE_BPS
+        || config.issuance_flat_fee > Config::MAX_FLAT_FEE
+        || config.redemption_flat_fee > Config::MAX_FLAT_FEE
+    {
+        msg!("Fee above cap");
+        return Err(GideonError::InvalidConfigSetting.into());
+    }
+    if config.max_voucher_amount == 0
+        || !(1..=Escrow::MAX_VALIDITY_PERIOD).contains(&config.max_validity_period)
+        || !(0..=Escrow::MAX_GRACE_PERIOD).contains(&config.max_grace_period)
+    {
+        msg!("Invalid limits");
+        return Err(GideonError::InvalidConfigSetting.into());
+    }
+
+    Ok(())
+}
+
+/// Reads the upgrade authority from an upgradeable loader `ProgramData` account.
+fn read_upgrade_authority(data: &[u8]) -> Option<Pubkey> {
+    // Layout: u32 variant (3 = ProgramData), u64 slot, Option<Pubkey> authority
+    let variant = u32::from_le_bytes(data.get(0..4)?.try_into().ok()?);
+    if variant != 3 || *data.get(12)? != 1 {
+        return None;
+    }
+
+    Pubkey::try_from(data.get(13..45)?).ok()
+}
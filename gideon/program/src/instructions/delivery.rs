@@ -0,0 +1,277 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::{
+    extension::StateWithExtensions, instruction as token_instruction, state::Account,
+};
+
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    instructions::{burn::check_voucher, receipt::create_receipt},
+    state::{
+        authority::MintAuthoritySigner,
+        config::Config,
+        escrow::{Escrow, Fulfilment},
+        merchant::Merchant,
+    },
+    utils::{check_token_program, close_account, transfer_lamports},
+};
+
+/// Records a merchant's claim to have fulfilled one use of a voucher that needs delivery
+/// confirmation. The value is only paid out once the holder confirms receipt, or the
+/// confirmation window passes without the holder rejecting the claim.
+///
+/// Only merchants registered with the voucher's issuer can claim.
+pub fn claim_fulfilment(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let merchant = next_account_info(accounts_iter)?;
+    let ata = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let _config = next_account_info(accounts_iter)?;
+    let merchant_registration = next_account_info(accounts_iter)?;
+
+    if !merchant.is_signer {
+        msg!("Merchant must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut escrow = Escrow::load(program_id, escrow_account)?;
+    Merchant::check_registered(
+        program_id,
+        merchant_registration,
+        &escrow.issuer,
+        merchant.key,
+    )?;
+    escrow.check_not_disputed()?;
+    if escrow.confirmation_window == 0 {
+        msg!("Voucher does not need delivery confirmation");
+        return Err(GideonError::DeliveryConfirmationNotRequired.into());
+    }
+    if escrow.fulfilment.is_some() {
+        msg!("Fulfilment already pending");
+        return Err(GideonError::FulfilmentPending.into());
+    }
+
+    // Any current holder of the voucher can receive the goods
+    check_voucher(&escrow, escrow_account, mint_account, ata, None, None)?;
+    let holder = {
+        let ata_data = ata.try_borrow_data()?;
+        StateWithExtensions::<Account>::unpack(&ata_data)?
+            .base
+            .owner
+    };
+
+    let now = Clock::get()?.unix_timestamp;
+    let fulfilment = Fulfilment {
+        merchant: *merchant.key,
+        holder,
+        amount: escrow.per_redemption_amount.min(escrow.amount),
+        claimed_at: now,
+    };
+
+    GideonEvent::FulfilmentClaimed {
+        voucher_mint: *mint_account.key,
+        escrow: *escrow_account.key,
+        merchant: fulfilment.merchant,
+        holder: fulfilment.holder,
+        amount: fulfilment.amount,
+        confirm_by: now.saturating_add(escrow.confirmation_window),
+    }
+    .emit();
+
+    msg!("Fulfilment claimed");
+    escrow.fulfilment = Some(fulfilment);
+    escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Lets the holder turn down a claimed fulfilment they didn't receive while the confirmation
+/// window is open, clearing it so nothing is paid out.
+pub fn reject_fulfilment(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let holder = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+
+    if !holder.is_signer {
+        msg!("Holder must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut escrow = Escrow::load(program_id, escrow_account)?;
+    escrow.check_not_disputed()?;
+    let Some(fulfilment) = escrow.fulfilment.take() else {
+        msg!("No fulfilment claimed");
+        return Err(GideonError::FulfilmentNotClaimed.into());
+    };
+    if *holder.key != fulfilment.holder {
+        msg!("Only the holder can reject a fulfilment");
+        return Err(GideonError::InvalidVoucherHolder.into());
+    }
+    let confirm_by = fulfilment
+        .claimed_at
+        .saturating_add(escrow.confirmation_window);
+    if Clock::get()?.unix_timestamp > confirm_by {
+        msg!("Confirmation window closed");
+        return Err(GideonError::ConfirmationWindowClosed.into());
+    }
+
+    GideonEvent::FulfilmentRejected {
+        voucher_mint: escrow.voucher_mint,
+        escrow: *escrow_account.key,
+        merchant: fulfilment.merchant,
+        holder: fulfilment.holder,
+        amount: fulfilment.amount,
+    }
+    .emit();
+
+    msg!("Fulfilment rejected");
+    escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Pays a claimed fulfilment to the merchant, either when the holder confirms receipt or,
+/// once the confirmation window has passed, at anyone's request.
+pub fn release_fulfilment(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let authority = next_account_info(accounts_iter)?;
+    let ata = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let merchant = next_account_info(accounts_iter)?;
+    let escrow_payer = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let receipt_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !authority.is_signer {
+        msg!("Authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let config = Config::load(program_id, config_account)?;
+    config.check_treasury(treasury)?;
+    check_token_program(token_program, &[mint_account, ata])?;
+
+    let mut escrow = Escrow::load(program_id, escrow_account)?;
+    escrow.check_not_disputed()?;
+    escrow.check_not_frozen()?;
+    let Some(fulfilment) = escrow.fulfilment.take() else {
+        msg!("No fulfilment claimed");
+        return Err(GideonError::FulfilmentNotClaimed.into());
+    };
+
+    // Only the holder can release early, anyone can once the window has passed
+    let confirm_by = fulfilment
+        .claimed_at
+        .saturating_add(escrow.confirmation_window);
+    if *authority.key != fulfilment.holder && Clock::get()?.unix_timestamp <= confirm_by {
+        msg!("Confirmation window still open");
+        return Err(GideonError::ConfirmationWindowOpen.into());
+    }
+
+    if *merchant.key != fulfilment.merchant {
+        msg!("Invalid merchant account");
+        return Err(GideonError::InvalidPayoutAccount.into());
+    }
+    if escrow.payer != *escrow_payer.key {
+        msg!("Invalid escrow payer account");
+        return Err(GideonError::InvalidIssuer.into());
+    }
+    if escrow.voucher_mint != *mint_account.key {
+        msg!("Invalid voucher mint account");
+        return Err(GideonError::InvalidVoucherEscrowAccount.into());
+    }
+
+    let amount = fulfilment.amount;
+    let redemption = escrow.next_redemption();
+    escrow.amount -= amount;
+    escrow.redemptions_remaining = escrow.redemptions_remaining.saturating_sub(1);
+    escrow.transition(escrow.redeemed_status(), Clock::get()?.unix_timestamp)?;
+
+    // Burn the voucher on its last use, through the program's permanent delegate
+    if escrow.redemptions_remaining == 0 {
+        let authority =
+            MintAuthoritySigner::load(program_id, mint_authority, &escrow.issuer, true)?;
+        invoke_signed(
+            &token_instruction::burn_checked(
+                token_program.key,
+                ata.key,
+                mint_account.key,
+                mint_authority.key,
+                &[mint_authority.key],
+                1,
+                0,
+            )?,
+            &[
+                ata.clone(),
+                mint_account.clone(),
+                mint_authority.clone(),
+                token_program.clone(),
+            ],
+            &[&authority.seeds()],
+        )?;
+    }
+
+    // Pay the fulfilled value, less the protocol fee, to the merchant
+    let fee = config.redemption_fee(amount);
+    transfer_lamports(escrow_account, treasury, fee)?;
+    transfer_lamports(escrow_account, merchant, amount - fee)?;
+    create_receipt(
+        program_id,
+        receipt_account,
+        authority,
+        system_program,
+        &escrow,
+        &fulfilment.holder,
+        merchant.key,
+        amount,
+        redemption,
+        None,
+        escrow.external_ref.as_deref(),
+    )?;
+
+    GideonEvent::FulfilmentReleased {
+        voucher_mint: *mint_account.key,
+        escrow: *escrow_account.key,
+        merchant: *merchant.key,
+        confirmed: *authority.key == fulfilment.holder,
+        amount,
+        fee,
+        redemptions_remaining: escrow.redemptions_remaining,
+    }
+    .emit();
+    GideonEvent::emit_external_ref(
+        *mint_account.key,
+        *escrow_account.key,
+        escrow.external_ref.clone(),
+    );
+
+    if escrow.redemptions_remaining > 0 {
+        msg!("Redemptions remaining: {}", escrow.redemptions_remaining);
+        escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+        return Ok(());
+    }
+
+    // Return the rent deposit to the escrow payer and close escrow account
+    close_account(escrow_account, escrow_payer)?;
+
+    Ok(())
+}
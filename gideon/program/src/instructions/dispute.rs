@@ -0,0 +1,270 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::{
+    extension::StateWithExtensions, instruction as token_instruction, state::Mint,
+};
+
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    state::{
+        authority::MintAuthoritySigner,
+        escrow::Escrow,
+        milestone::{MilestoneSchedule, MilestoneStatus},
+        status::VoucherStatus,
+    },
+    utils::{check_token_program, close_account, transfer_lamports},
+};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct ResolveDisputeArgs {
+    pub to_payer: u64,
+    pub to_recipient: u64,
+    pub to_merchant: u64,
+}
+
+/// Freezes the escrow until its arbiter settles the dispute, or the dispute times out.
+///
+/// Either the payer or the recipient can raise a dispute while the voucher can still be
+/// redeemed, as long as an arbiter was named at issuance. A voucher can be disputed at most
+/// `Escrow::MAX_DISPUTES` times.
+pub fn open_dispute(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let party = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+
+    if !party.is_signer {
+        msg!("Disputing party must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut escrow = Escrow::load(program_id, escrow_account)?;
+    if *party.key != escrow.payer && *party.key != escrow.recipient {
+        msg!("Only the payer or recipient can open a dispute");
+        return Err(GideonError::InvalidRecipientAccount.into());
+    }
+    if escrow.arbiter.is_none() {
+        msg!("Voucher has no arbiter");
+        return Err(GideonError::InvalidArbiter.into());
+    }
+    escrow.check_not_disputed()?;
+    if escrow.disputes_opened >= Escrow::MAX_DISPUTES {
+        msg!("Voucher disputed too many times");
+        return Err(GideonError::TooManyDisputes.into());
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    if now > escrow.settlement_deadline() {
+        msg!("Voucher expired");
+        return Err(GideonError::VoucherExpired.into());
+    }
+
+    msg!("Opening dispute");
+    escrow.transition(VoucherStatus::Disputed, now)?;
+    escrow.disputed_at = Some(now);
+    escrow.disputes_opened += 1;
+    escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+
+    GideonEvent::DisputeOpened {
+        voucher_mint: escrow.voucher_mint,
+        escrow: *escrow_account.key,
+        opened_by: *party.key,
+        timestamp: now,
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Lets the arbiter split the escrowed value between the payer, the recipient and a
+/// merchant, taking the arbiter fee, then burns the voucher and closes the escrow.
+pub fn resolve_dispute(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: ResolveDisputeArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let arbiter = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let escrow_payer = next_account_info(accounts_iter)?;
+    let payout = next_account_info(accounts_iter)?;
+    let merchant = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let token_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let _config = next_account_info(accounts_iter)?;
+
+    if !arbiter.is_signer {
+        msg!("Arbiter must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    check_token_program(token_program, &[mint_account])?;
+
+    let mut escrow = Escrow::load(program_id, escrow_account)?;
+    if escrow.arbiter != Some(*arbiter.key) {
+        msg!("Invalid arbiter account");
+        return Err(GideonError::InvalidArbiter.into());
+    }
+    let Some(disputed_at) = escrow.disputed_at else {
+        msg!("Escrow not disputed");
+        return Err(GideonError::EscrowNotDisputed.into());
+    };
+    let now = Clock::get()?.unix_timestamp;
+    if now > disputed_at.saturating_add(Escrow::DISPUTE_TIMEOUT) {
+        msg!("Dispute timed out");
+        return Err(GideonError::DisputeTimedOut.into());
+    }
+
+    // Check the payer, who also gets the rent deposit back, and the recipient's payout account
+    if escrow.payer != *escrow_payer.key {
+        msg!("Invalid escrow payer account");
+        return Err(GideonError::InvalidIssuer.into());
+    }
+    if *payout.key != escrow.payout_address.unwrap_or(escrow.recipient) {
+        msg!("Invalid payout account");
+        return Err(GideonError::InvalidPayoutAccount.into());
+    }
+    if escrow.voucher_mint != *mint_account.key {
+        msg!("Invalid mint account");
+        return Err(GideonError::InvalidVoucherEscrowAccount.into());
+    }
+
+    // Check the split and the arbiter fee add up to the escrowed amount
+    let arbiter_fee = (escrow.amount as u128 * escrow.arbiter_fee_bps as u128 / 10_000) as u64;
+    let total = args
+        .to_payer
+        .checked_add(args.to_recipient)
+        .and_then(|total| total.checked_add(args.to_merchant))
+        .and_then(|total| total.checked_add(arbiter_fee));
+    if total != Some(escrow.amount) {
+        msg!("Invalid dispute settlement");
+        return Err(GideonError::InvalidDisputeSettlement.into());
+    }
+
+    msg!("Resolving dispute");
+    escrow.transition(VoucherStatus::Cancelled, now)?;
+    transfer_lamports(escrow_account, arbiter, arbiter_fee)?;
+    transfer_lamports(escrow_account, payout, args.to_recipient)?;
+    transfer_lamports(escrow_account, merchant, args.to_merchant)?;
+
+    // Retire the voucher NFT through the program's permanent delegate, unless the holder
+    // already burned it, so the settled voucher can't be presented again
+    let supply = StateWithExtensions::<Mint>::unpack(&mint_account.try_borrow_data()?)?
+        .base
+        .supply;
+    if supply > 0 {
+        msg!("Burning disputed voucher");
+        check_token_program(token_program, &[token_account])?;
+        let authority =
+            MintAuthoritySigner::load(program_id, mint_authority, &escrow.issuer, true)?;
+        invoke_signed(
+            &token_instruction::burn_checked(
+                token_program.key,
+                token_account.key,
+                mint_account.key,
+                mint_authority.key,
+                &[mint_authority.key],
+                1,
+                0,
+            )?,
+            &[
+                token_account.clone(),
+                mint_account.clone(),
+                mint_authority.clone(),
+                token_program.clone(),
+            ],
+            &[&authority.seeds()],
+        )?;
+    }
+
+    // Milestone escrows also return the schedule's rent deposit
+    if escrow.milestone_count > 0 {
+        let schedule_account = next_account_info(accounts_iter)?;
+        MilestoneSchedule::load(program_id, schedule_account, escrow_account.key)?;
+        close_account(schedule_account, escrow_payer)?;
+    }
+
+    GideonEvent::DisputeResolved {
+        voucher_mint: escrow.voucher_mint,
+        escrow: *escrow_account.key,
+        arbiter: *arbiter.key,
+        merchant: *merchant.key,
+        to_payer: args.to_payer,
+        to_recipient: args.to_recipient,
+        to_merchant: args.to_merchant,
+        arbiter_fee,
+    }
+    .emit();
+
+    // The payer's share and the rent deposit are what remains in the escrow
+    close_account(escrow_account, escrow_payer)?;
+
+    Ok(())
+}
+
+/// Lifts a dispute the arbiter failed to settle in time, so the voucher can be redeemed or
+/// reclaimed as before. The validity window, any confirmation window and the deadlines of
+/// pending milestones are extended by the time spent frozen.
+pub fn lapse_dispute(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let escrow_account = next_account_info(accounts_iter)?;
+    let _config = next_account_info(accounts_iter)?;
+
+    let mut escrow = Escrow::load(program_id, escrow_account)?;
+    let Some(disputed_at) = escrow.disputed_at else {
+        msg!("Escrow not disputed");
+        return Err(GideonError::EscrowNotDisputed.into());
+    };
+    let now = Clock::get()?.unix_timestamp;
+    if now <= disputed_at.saturating_add(Escrow::DISPUTE_TIMEOUT) {
+        msg!("Dispute not timed out");
+        return Err(GideonError::DisputeNotTimedOut.into());
+    }
+
+    msg!("Lapsing dispute");
+    let frozen_for = now.saturating_sub(disputed_at);
+    escrow.valid_until = escrow.valid_until.saturating_add(frozen_for);
+    if let Some(fulfilment) = escrow.fulfilment.as_mut() {
+        fulfilment.claimed_at = fulfilment.claimed_at.saturating_add(frozen_for);
+    }
+    escrow.transition(escrow.live_status(now), now)?;
+    escrow.disputed_at = None;
+    escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+
+    // Milestone escrows also give the payer back the time to approve each pending tranche
+    if escrow.milestone_count > 0 {
+        let schedule_account = next_account_info(accounts_iter)?;
+        let mut schedule =
+            MilestoneSchedule::load(program_id, schedule_account, escrow_account.key)?;
+        for milestone in schedule
+            .milestones
+            .iter_mut()
+            .filter(|milestone| milestone.status == MilestoneStatus::Pending)
+        {
+            milestone.deadline = milestone.deadline.saturating_add(frozen_for);
+        }
+        schedule.serialize(&mut &mut schedule_account.data.borrow_mut()[..])?;
+    }
+
+    GideonEvent::DisputeLapsed {
+        voucher_mint: escrow.voucher_mint,
+        escrow: *escrow_account.key,
+        valid_until: escrow.valid_until,
+    }
+    .emit();
+
+    Ok(())
+}
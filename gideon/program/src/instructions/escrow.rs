@@ -2,16 +2,29 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     borsh1::try_from_slice_unchecked,
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
     program::{invoke, invoke_signed},
+    program_error::ProgramError,
     pubkey::Pubkey,
     rent::Rent,
     system_instruction,
     sysvar::Sysvar,
 };
 
-use crate::state::escrow::Escrow;
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    instructions::milestone::{init_milestones, MilestoneArgs},
+    oracle::Price,
+    state::{
+        config::Config,
+        escrow::{Escrow, FiatTerms},
+        issuer::Issuer,
+        status::VoucherStatus,
+    },
+};
 
 #[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
 pub struct InitEscrowArgs {
@@ -19,6 +32,29 @@ pub struct InitEscrowArgs {
     pub recipient: Pubkey,
     pub amount: u64,
     pub voucher_mint: Pubkey,
+    pub valid_from: i64,
+    pub valid_until: i64,
+    pub grace_period: i64,
+    pub max_redemptions: u16,
+    pub per_redemption_amount: u64,
+    /// Hash of a high-entropy redemption code, from `redemption_code_hash`
+    pub code_hash: Option<[u8; 32]>,
+    pub arbiter: Option<Pubkey>,
+    pub arbiter_fee_bps: u16,
+    /// Tranches released as the payer approves each one, empty for a regular voucher
+    pub milestones: Vec<MilestoneArgs>,
+    /// Seconds the holder has to confirm delivery of a redemption, zero to pay out directly
+    pub confirmation_window: i64,
+    /// Address refunded instead of the payer if the voucher expires unused
+    pub refund_address: Option<Pubkey>,
+    /// Lamports paid to whoever reclaims the escrow once it expires, on top of the amount
+    pub crank_bounty: u64,
+    /// Keep freeze authority with the program so the issuer can freeze the voucher
+    pub freezable: bool,
+    /// Face value in a fiat currency, with the amount held as SOL collateral for it
+    pub fiat: Option<FiatTerms>,
+    /// Partner order or tracking reference, recorded on the escrow, events and receipts
+    pub external_ref: Option<Vec<u8>>,
 }
 
 pub fn init_escrow(
@@ -37,6 +73,115 @@ pub fn init_escrow(
     let system_program = next_account_info(accounts_iter)?;
     let _token_program = next_account_info(accounts_iter)?;
     let _associated_token_program = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let issuer_account = next_account_info(accounts_iter)?;
+    let milestones_account = if args.milestones.is_empty() {
+        None
+    } else {
+        Some(next_account_info(accounts_iter)?)
+    };
+    let price_feed = if args.fiat.is_none() {
+        None
+    } else {
+        Some(next_account_info(accounts_iter)?)
+    };
+
+    let config = Config::load(program_id, config_account)?;
+    config.check_treasury(treasury)?;
+
+    // Check the payer may issue vouchers for the issuer
+    let issuer = Issuer::load(program_id, issuer_account)?;
+    if !issuer.is_signer(payer.key) {
+        msg!("Payer not authorised for issuer");
+        return Err(GideonError::UnauthorizedIssuerSigner.into());
+    }
+
+    // Check amount against the platform limit
+    if args.amount > config.max_voucher_amount {
+        msg!("Voucher amount above limit");
+        return Err(GideonError::VoucherAmountTooLarge.into());
+    }
+
+    // Check validity window
+    let now = Clock::get()?.unix_timestamp;
+    if args.valid_until <= args.valid_from || args.valid_until <= now {
+        msg!("Invalid validity window");
+        return Err(GideonError::InvalidValidityWindow.into());
+    }
+    if args.valid_until.saturating_sub(now) > config.max_validity_period {
+        msg!("Validity window too long");
+        return Err(GideonError::InvalidValidityWindow.into());
+    }
+    if !(0..=config.max_grace_period).contains(&args.grace_period) {
+        msg!("Invalid grace period");
+        return Err(GideonError::InvalidGracePeriod.into());
+    }
+
+    // Check redemption configuration, the uses must add up to the escrowed amount
+    if args.max_redemptions == 0
+        || args.per_redemption_amount == 0
+        || args
+            .per_redemption_amount
+            .checked_mul(args.max_redemptions as u64)
+            != Some(args.amount)
+    {
+        msg!("Invalid redemption configuration");
+        return Err(GideonError::InvalidRedemptionConfig.into());
+    }
+
+    // Check arbiter, who must be a third party to the voucher
+    if args.arbiter.is_none() && args.arbiter_fee_bps > 0
+        || args.arbiter_fee_bps > Escrow::MAX_ARBITER_FEE_BPS
+        || args.arbiter == Some(args.payer)
+        || args.arbiter == Some(args.recipient)
+    {
+        msg!("Invalid arbiter");
+        return Err(GideonError::InvalidArbiter.into());
+    }
+
+    // Check delivery confirmation window
+    if !(0..=Escrow::MAX_CONFIRMATION_WINDOW).contains(&args.confirmation_window)
+        || args.confirmation_window > 0 && !args.milestones.is_empty()
+    {
+        msg!("Invalid confirmation window");
+        return Err(GideonError::InvalidConfirmationWindow.into());
+    }
+
+    Escrow::check_external_ref(args.external_ref.as_deref())?;
+
+    if args.crank_bounty > Escrow::MAX_CRANK_BOUNTY {
+        msg!("Crank bounty above limit");
+        return Err(GideonError::InvalidCrankBounty.into());
+    }
+
+    // Check fiat terms, settled in a single redemption by the holder against a collateral
+    // worth more than the face value at today's price
+    if let (Some(fiat), Some(price_feed)) = (&args.fiat, price_feed) {
+        if args.max_redemptions != 1
+            || !args.milestones.is_empty()
+            || args.confirmation_window > 0
+            || args.code_hash.is_some()
+            || fiat.face_value == 0
+            || fiat.decimals > FiatTerms::MAX_DECIMALS
+            || !fiat.currency.iter().all(u8::is_ascii_uppercase)
+        {
+            msg!("Invalid fiat terms");
+            return Err(GideonError::InvalidFiatTerms.into());
+        }
+        if *price_feed.key != fiat.price_feed {
+            msg!("Invalid price feed account");
+            return Err(GideonError::InvalidPriceFeed.into());
+        }
+
+        let value = Price::load(price_feed, &config.oracle_program, now)?
+            .lamports_for(fiat.face_value, fiat.decimals)?;
+        if (args.amount as u128) * 10_000 < value as u128 * Escrow::MIN_FIAT_COLLATERAL_BPS as u128
+        {
+            msg!("Fiat value not over-collateralised");
+            return Err(GideonError::InvalidFiatTerms.into());
+        }
+    }
 
     let (_escrow_pda, escrow_bump) = Pubkey::find_program_address(
         &[
@@ -85,13 +230,51 @@ pub fn init_escrow(
     account_data.amount = args.amount;
     account_data.bump = escrow_bump;
     account_data.voucher_mint = args.voucher_mint;
+    account_data.valid_from = args.valid_from;
+    account_data.valid_until = args.valid_until;
+    account_data.grace_period = args.grace_period;
+    account_data.max_redemptions = args.max_redemptions;
+    account_data.redemptions_remaining = args.max_redemptions;
+    account_data.per_redemption_amount = args.per_redemption_amount;
+    account_data.code_hash = args.code_hash;
+    account_data.issuer = *issuer_account.key;
+    account_data.arbiter = args.arbiter;
+    account_data.arbiter_fee_bps = args.arbiter_fee_bps;
+    account_data.disputed_at = None;
+    account_data.disputes_opened = 0;
+    account_data.milestone_count = args.milestones.len() as u8;
+    account_data.confirmation_window = args.confirmation_window;
+    account_data.fulfilment = None;
+    account_data.refund_address = args.refund_address;
+    account_data.crank_bounty = args.crank_bounty;
+    account_data.status = VoucherStatus::Issued;
+    account_data.freezable = args.freezable;
+    account_data.fiat = args.fiat;
+    account_data.external_ref = args.external_ref.clone();
+    account_data.original_seeds = None;
 
     account_data.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
 
-    // Invoke transfer to move the funds to the escrow account
+    if let Some(milestones_account) = milestones_account {
+        init_milestones(
+            program_id,
+            &account_data,
+            escrow_account,
+            milestones_account,
+            payer,
+            system_program,
+            args.milestones,
+        )?;
+    }
+
+    // Invoke transfer to move the funds and crank bounty to the escrow account
     msg!("Transferring funds to escrow account");
+    let escrow_funding = args
+        .amount
+        .checked_add(args.crank_bounty)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
     invoke(
-        &system_instruction::transfer(&args.payer, escrow_account.key, args.amount),
+        &system_instruction::transfer(&args.payer, escrow_account.key, escrow_funding),
         &[
             payer.clone(),
             escrow_account.clone(),
@@ -99,5 +282,79 @@ pub fn init_escrow(
         ],
     )?;
 
+    // Charge the issuance fee on top of the escrowed amount
+    let fee = config.issuance_fee(args.amount);
+    if fee > 0 {
+        msg!("Transferring issuance fee to treasury");
+        invoke(
+            &system_instruction::transfer(payer.key, treasury.key, fee),
+            &[payer.clone(), treasury.clone(), system_program.clone()],
+        )?;
+    }
+
+    GideonEvent::VoucherIssued {
+        voucher_mint: args.voucher_mint,
+        escrow: *escrow_account.key,
+        issuer: *issuer_account.key,
+        payer: args.payer,
+        recipient: args.recipient,
+        amount: args.amount,
+        fee,
+    }
+    .emit();
+    GideonEvent::emit_external_ref(args.voucher_mint, *escrow_account.key, args.external_ref);
+
+    Ok(())
+}
+
+/// Creates the escrow PDA for `escrow`'s payer, recipient and voucher mint, with `funder`
+/// paying the rent, and writes the escrow data.
+pub(crate) fn create_escrow<'a>(
+    program_id: &Pubkey,
+    escrow_account: &AccountInfo<'a>,
+    funder: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    mut escrow: Escrow,
+) -> ProgramResult {
+    let (escrow_pda, escrow_bump) = Pubkey::find_program_address(
+        &[
+            Escrow::SEED_PREFIX.as_bytes(),
+            escrow.payer.as_ref(),
+            escrow.recipient.as_ref(),
+            escrow.voucher_mint.as_ref(),
+        ],
+        program_id,
+    );
+    if escrow_account.key != &escrow_pda {
+        msg!("Invalid voucher escrow account");
+        return Err(GideonError::InvalidVoucherEscrowAccount.into());
+    }
+
+    msg!("Creating escrow account");
+    invoke_signed(
+        &system_instruction::create_account(
+            funder.key,
+            escrow_account.key,
+            Rent::get()?.minimum_balance(Escrow::ACCOUNT_SPACE),
+            Escrow::ACCOUNT_SPACE as u64,
+            program_id,
+        ),
+        &[
+            funder.clone(),
+            escrow_account.clone(),
+            system_program.clone(),
+        ],
+        &[&[
+            Escrow::SEED_PREFIX.as_bytes(),
+            escrow.payer.as_ref(),
+            escrow.recipient.as_ref(),
+            escrow.voucher_mint.as_ref(),
+            &[escrow_bump],
+        ]],
+    )?;
+
+    escrow.bump = escrow_bump;
+    escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+
     Ok(())
 }
@@ -4,36 +4,78 @@ use solana_program::{
     clock::Clock,
     entrypoint::ProgramResult,
     msg,
+    program::invoke_signed,
+    program_error::ProgramError,
     pubkey::Pubkey,
     sysvar::Sysvar,
 };
 use spl_token_2022::{
-    extension::{BaseStateWithExtensions, StateWithExtensions},
+    extension::{
+        permanent_delegate::PermanentDelegate, BaseStateWithExtensions, StateWithExtensions,
+    },
+    instruction as token_instruction,
     state::Mint,
 };
 use spl_token_metadata_interface::state::TokenMetadata;
 
-use crate::{error::GideonError, state::escrow::Escrow};
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    state::{
+        authority::MintAuthoritySigner, escrow::Escrow, milestone::MilestoneSchedule,
+        status::VoucherStatus,
+    },
+    utils::{check_token_program, close_account, transfer_lamports},
+};
 
-pub fn release_expired_escrow(accounts: &[AccountInfo]) -> ProgramResult {
+/// Reclaims an expired voucher's escrow. Anyone can crank it: the funds always go to the
+/// payer or their refund address, and the cranker is paid the escrow's crank bounty.
+pub fn release_expired_escrow(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
-    let payer = next_account_info(accounts_iter)?;
+    let cranker = next_account_info(accounts_iter)?;
     let escrow_account = next_account_info(accounts_iter)?;
     let mint_account = next_account_info(accounts_iter)?;
-    let clock_program = next_account_info(accounts_iter)?;
+    let _clock_program = next_account_info(accounts_iter)?;
+    let _config = next_account_info(accounts_iter)?;
+    let refund = next_account_info(accounts_iter)?;
+    let token_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !cranker.is_signer {
+        msg!("Cranker must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    check_token_program(token_program, &[mint_account])?;
+
+    // Check the refund goes where the payer wants it
+    let mut escrow = Escrow::load(program_id, escrow_account)?;
+    if *refund.key != escrow.refund_address.unwrap_or(escrow.payer) {
+        msg!("Invalid refund account");
+        return Err(GideonError::InvalidRefundAccount.into());
+    }
+    if escrow.voucher_mint != *mint_account.key {
+        msg!("Invalid mint account");
+        return Err(GideonError::InvalidVoucherEscrowAccount.into());
+    }
+    escrow.check_not_disputed()?;
+    if escrow.fulfilment.is_some() {
+        msg!("Fulfilment pending");
+        return Err(GideonError::FulfilmentPending.into());
+    }
 
-    // Check escrow recipient
-    let escrow_data = escrow_account.data.borrow();
-    let escrow = try_from_slice_unchecked::<Escrow>(&escrow_data).unwrap();
-    if escrow.payer != *payer.key {
-        msg!("Invalid payer account");
-        return Err(GideonError::InvalidIssuer.into());
+    // Check voucher expiry, allowing merchants the grace period to settle
+    let now = Clock::get()?.unix_timestamp;
+    if now <= escrow.settlement_deadline() {
+        msg!("Voucher not expired");
+        return Err(GideonError::VoucherNotExpired.into());
     }
-    drop(escrow_data);
+    let frozen = escrow.status == VoucherStatus::Frozen;
+    escrow.transition(VoucherStatus::Expired, now)?;
 
     // Get token metadata
-    {
+    let (supply, has_permanent_delegate) = {
         let mint_data = mint_account.try_borrow_data()?;
         let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
         let metadata_bytes = mint.get_extension_bytes::<TokenMetadata>()?;
@@ -51,33 +93,85 @@ pub fn release_expired_escrow(accounts: &[AccountInfo]) -> ProgramResult {
             return Err(GideonError::InvalidVoucherEscrowAccount.into());
         }
 
-        // Check voucher expiry
-        let voucher_expiry = metadata
-            .additional_metadata
-            .iter()
-            .find(|(key, _)| key == "expiry")
-            .map(|(_, value)| value.parse::<i64>().unwrap())
-            .unwrap()
-            / 1000;
-        let clock = Clock::from_account_info(clock_program)?;
-        if clock.unix_timestamp < voucher_expiry {
-            msg!("Voucher not expired");
-            return Err(GideonError::VoucherNotExpired.into());
+        (
+            mint.base.supply,
+            mint.get_extension::<PermanentDelegate>().is_ok(),
+        )
+    };
+
+    // Retire the voucher NFT through the program's permanent delegate, unless the holder
+    // already burned it. A frozen voucher is thawed first, so it can still be reclaimed.
+    // Legacy vouchers were minted without a permanent delegate, so they stay with their
+    // holder, unbacked once the escrow is closed
+    if supply > 0 && !has_permanent_delegate {
+        msg!("Voucher has no permanent delegate, leaving it with its holder");
+    } else if supply > 0 {
+        msg!("Burning expired voucher");
+        check_token_program(token_program, &[token_account])?;
+        let authority =
+            MintAuthoritySigner::load(program_id, mint_authority, &escrow.issuer, true)?;
+        if frozen {
+            invoke_signed(
+                &token_instruction::thaw_account(
+                    token_program.key,
+                    token_account.key,
+                    mint_account.key,
+                    mint_authority.key,
+                    &[mint_authority.key],
+                )?,
+                &[
+                    token_account.clone(),
+                    mint_account.clone(),
+                    mint_authority.clone(),
+                    token_program.clone(),
+                ],
+                &[&authority.seeds()],
+            )?;
         }
+        invoke_signed(
+            &token_instruction::burn_checked(
+                token_program.key,
+                token_account.key,
+                mint_account.key,
+                mint_authority.key,
+                &[mint_authority.key],
+                1,
+                0,
+            )?,
+            &[
+                token_account.clone(),
+                mint_account.clone(),
+                mint_authority.clone(),
+                token_program.clone(),
+            ],
+            &[&authority.seeds()],
+        )?;
+    }
 
-        drop(mint_data);
+    // Milestone escrows also return the schedule's rent deposit
+    if escrow.milestone_count > 0 {
+        let schedule_account = next_account_info(accounts_iter)?;
+        MilestoneSchedule::load(program_id, schedule_account, escrow_account.key)?;
+        close_account(schedule_account, refund)?;
     }
 
-    // Transfer lamports from escrow to payer and close escrow account
-    let payer_lamports = payer.lamports();
-    **payer.lamports.borrow_mut() = payer_lamports
-        .checked_add(escrow_account.lamports())
-        .unwrap();
-    **escrow_account.lamports.borrow_mut() = 0;
+    if escrow.crank_bounty > 0 {
+        msg!("Paying crank bounty");
+        transfer_lamports(escrow_account, cranker, escrow.crank_bounty)?;
+    }
+
+    GideonEvent::EscrowReclaimed {
+        voucher_mint: escrow.voucher_mint,
+        escrow: *escrow_account.key,
+        refund: *refund.key,
+        cranker: *cranker.key,
+        amount: escrow.amount,
+        bounty: escrow.crank_bounty,
+    }
+    .emit();
 
-    // Clear escrow account data
-    let mut escrow_data = escrow_account.data.borrow_mut();
-    escrow_data.fill(0);
+    // Return the voucher value and rent deposit to the payer and close escrow account
+    close_account(escrow_account, refund)?;
 
     Ok(())
 }
@@ -0,0 +1,203 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::{
+    extension::StateWithExtensions, instruction as token_instruction, state::Account,
+};
+
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    state::{
+        authority::MintAuthoritySigner, config::Config, escrow::Escrow, issuer::Issuer,
+        status::VoucherStatus,
+    },
+    utils::check_token_program,
+};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct FreezeVoucherArgs {
+    /// Issuer-defined code for why the voucher was frozen, e.g. reported stolen
+    pub reason_code: u16,
+}
+
+/// Freezes a voucher issued as freezable, e.g. when it is reported stolen. The holder can
+/// neither move nor redeem it until it is thawed.
+pub fn freeze_voucher(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: FreezeVoucherArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let authority = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let token_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let issuer_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    check_token_program(token_program, &[mint_account, token_account])?;
+
+    let mut escrow = load_freezable_escrow(
+        program_id,
+        authority,
+        escrow_account,
+        mint_account,
+        token_account,
+        issuer_account,
+        config_account,
+    )?;
+    escrow.check_not_disputed()?;
+    if escrow.fulfilment.is_some() {
+        msg!("Fulfilment pending");
+        return Err(GideonError::FulfilmentPending.into());
+    }
+    escrow.transition(VoucherStatus::Frozen, Clock::get()?.unix_timestamp)?;
+
+    msg!("Freezing voucher");
+    let signer = MintAuthoritySigner::load(program_id, mint_authority, &escrow.issuer, false)?;
+    invoke_signed(
+        &token_instruction::freeze_account(
+            token_program.key,
+            token_account.key,
+            mint_account.key,
+            mint_authority.key,
+            &[mint_authority.key],
+        )?,
+        &[
+            token_account.clone(),
+            mint_account.clone(),
+            mint_authority.clone(),
+            token_program.clone(),
+        ],
+        &[&signer.seeds()],
+    )?;
+    escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+
+    GideonEvent::VoucherFrozen {
+        voucher_mint: escrow.voucher_mint,
+        escrow: *escrow_account.key,
+        authority: *authority.key,
+        reason_code: args.reason_code,
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Thaws a frozen voucher, returning it to the status it had before.
+pub fn thaw_voucher(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let authority = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let token_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let issuer_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    check_token_program(token_program, &[mint_account, token_account])?;
+
+    let mut escrow = load_freezable_escrow(
+        program_id,
+        authority,
+        escrow_account,
+        mint_account,
+        token_account,
+        issuer_account,
+        config_account,
+    )?;
+    if escrow.status != VoucherStatus::Frozen {
+        msg!("Voucher not frozen");
+        return Err(GideonError::VoucherNotFrozen.into());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    escrow.transition(escrow.live_status(now), now)?;
+
+    msg!("Thawing voucher");
+    let signer = MintAuthoritySigner::load(program_id, mint_authority, &escrow.issuer, false)?;
+    invoke_signed(
+        &token_instruction::thaw_account(
+            token_program.key,
+            token_account.key,
+            mint_account.key,
+            mint_authority.key,
+            &[mint_authority.key],
+        )?,
+        &[
+            token_account.clone(),
+            mint_account.clone(),
+            mint_authority.clone(),
+            token_program.clone(),
+        ],
+        &[&signer.seeds()],
+    )?;
+    escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+
+    GideonEvent::VoucherThawed {
+        voucher_mint: escrow.voucher_mint,
+        escrow: *escrow_account.key,
+        authority: *authority.key,
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Loads a freezable escrow, checking `authority` is a signer of its issuer or the platform
+/// admin, and that `token_account` holds its voucher.
+fn load_freezable_escrow(
+    program_id: &Pubkey,
+    authority: &AccountInfo,
+    escrow_account: &AccountInfo,
+    mint_account: &AccountInfo,
+    token_account: &AccountInfo,
+    issuer_account: &AccountInfo,
+    config_account: &AccountInfo,
+) -> Result<Escrow, ProgramError> {
+    if !authority.is_signer {
+        msg!("Authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let escrow = Escrow::load(program_id, escrow_account)?;
+    if !escrow.freezable {
+        msg!("Voucher not freezable");
+        return Err(GideonError::VoucherNotFreezable.into());
+    }
+
+    let config = Config::load(program_id, config_account)?;
+    if *authority.key != config.admin {
+        let issuer = Issuer::load(program_id, issuer_account)?;
+        if escrow.issuer != *issuer_account.key || !issuer.is_signer(authority.key) {
+            msg!("Unauthorized issuer signer");
+            return Err(GideonError::UnauthorizedIssuerSigner.into());
+        }
+    }
+
+    if escrow.voucher_mint != *mint_account.key {
+        msg!("Invalid voucher mint account");
+        return Err(GideonError::InvalidVoucherEscrowAccount.into());
+    }
+    {
+        let token_data = token_account.try_borrow_data()?;
+        let voucher = StateWithExtensions::<Account>::unpack(&token_data)?;
+        if voucher.base.mint != *mint_account.key || voucher.base.amount != 1 {
+            msg!("Invalid voucher holder");
+            return Err(GideonError::InvalidVoucherHolder.into());
+        }
+    }
+
+    Ok(escrow)
+}
@@ -0,0 +1,352 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    instructions::{
+        escrow::create_escrow,
+        mint::{mint_voucher, MintVoucherArgs},
+    },
+    state::{
+        config::Config,
+        escrow::Escrow,
+        grant::{GrantNonce, GrantVault},
+        issuer::Issuer,
+    },
+    utils::{create_pda_account, transfer_lamports, verify_ed25519_signature},
+};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct GrantVaultArgs {
+    pub title: String,
+    pub description: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct WithdrawGrantVaultArgs {
+    pub amount: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct RedeemGrantArgs {
+    pub amount: u64,
+    pub expiry: i64,
+    pub nonce: u64,
+}
+
+/// The message an issuer signs off-chain to grant a voucher to a recipient.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct VoucherGrant {
+    pub issuer: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub expiry: i64,
+    pub nonce: u64,
+}
+
+impl VoucherGrant {
+    pub const MESSAGE_PREFIX: &'static [u8] = b"gideon:grant:";
+
+    /// Bytes the issuer signs: a domain prefix followed by the borsh-encoded grant.
+    pub fn to_message(&self) -> Vec<u8> {
+        let mut message = Self::MESSAGE_PREFIX.to_vec();
+        message.extend_from_slice(&borsh::to_vec(self).unwrap());
+        message
+    }
+}
+
+/// Creates the issuer's grant vault, along with the metadata given to vouchers minted from it.
+pub fn init_grant_vault(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: GrantVaultArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let issuer_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    let issuer = Issuer::load(program_id, issuer_account)?;
+    if !authority.is_signer || issuer.authority != *authority.key {
+        msg!("Invalid issuer authority");
+        return Err(GideonError::InvalidIssuerAccount.into());
+    }
+
+    if args.title.len() > GrantVault::MAX_TITLE_LEN
+        || args.description.len() > GrantVault::MAX_DESCRIPTION_LEN
+        || args.symbol.len() > GrantVault::MAX_SYMBOL_LEN
+        || args.uri.len() > GrantVault::MAX_URI_LEN
+    {
+        msg!("Invalid grant vault metadata");
+        return Err(GideonError::InvalidGrantVault.into());
+    }
+
+    let (vault_pda, vault_bump) = Pubkey::find_program_address(
+        &[
+            GrantVault::SEED_PREFIX.as_bytes(),
+            issuer_account.key.as_ref(),
+        ],
+        program_id,
+    );
+    if vault_account.key != &vault_pda {
+        msg!("Invalid grant vault account");
+        return Err(GideonError::InvalidGrantVault.into());
+    }
+
+    msg!("Creating grant vault account");
+    create_pda_account(
+        authority,
+        vault_account,
+        system_program,
+        GrantVault::ACCOUNT_SPACE,
+        program_id,
+        &[
+            GrantVault::SEED_PREFIX.as_bytes(),
+            issuer_account.key.as_ref(),
+            &[vault_bump],
+        ],
+    )?;
+
+    let vault = GrantVault {
+        issuer: *issuer_account.key,
+        title: args.title,
+        description: args.description,
+        symbol: args.symbol,
+        uri: args.uri,
+        bump: vault_bump,
+    };
+    vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Returns funds from the grant vault to the issuer authority, keeping the vault open.
+pub fn withdraw_grant_vault(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: WithdrawGrantVaultArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let vault_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let issuer_account = next_account_info(accounts_iter)?;
+    let _config = next_account_info(accounts_iter)?;
+
+    let issuer = Issuer::load(program_id, issuer_account)?;
+    if !authority.is_signer || issuer.authority != *authority.key {
+        msg!("Invalid issuer authority");
+        return Err(GideonError::InvalidIssuerAccount.into());
+    }
+    let vault = GrantVault::load(program_id, vault_account)?;
+    if vault.issuer != *issuer_account.key {
+        msg!("Invalid grant vault account");
+        return Err(GideonError::InvalidGrantVault.into());
+    }
+
+    check_vault_balance(vault_account, args.amount)?;
+
+    msg!("Withdrawing from grant vault");
+    transfer_lamports(vault_account, authority, args.amount)?;
+
+    Ok(())
+}
+
+/// Mints the voucher an issuer granted off-chain to the signing recipient, funding its
+/// escrow from the issuer's grant vault.
+///
+/// The grant must be signed by the issuer's authority or one of its signers, in an Ed25519
+/// instruction placed right before this one. Each grant nonce can only be redeemed once.
+pub fn redeem_grant(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: RedeemGrantArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let escrow_account = next_account_info(accounts_iter)?;
+    let recipient = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let _mint_authority = next_account_info(accounts_iter)?;
+    let _associated_token_account = next_account_info(accounts_iter)?;
+    let _rent = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let _token_program = next_account_info(accounts_iter)?;
+    let _associated_token_program = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let issuer_account = next_account_info(accounts_iter)?;
+    let issuer_signer = next_account_info(accounts_iter)?;
+    let vault_account = next_account_info(accounts_iter)?;
+    let nonce_account = next_account_info(accounts_iter)?;
+    let instructions_sysvar = next_account_info(accounts_iter)?;
+
+    if !recipient.is_signer {
+        msg!("Recipient must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let config = Config::load(program_id, config_account)?;
+    config.check_treasury(treasury)?;
+
+    // Check the grant was signed by a key registered for the issuer
+    let issuer = Issuer::load(program_id, issuer_account)?;
+    if !issuer.is_signer(issuer_signer.key) {
+        msg!("Unauthorized issuer signer");
+        return Err(GideonError::UnauthorizedIssuerSigner.into());
+    }
+    let grant = VoucherGrant {
+        issuer: *issuer_account.key,
+        recipient: *recipient.key,
+        amount: args.amount,
+        expiry: args.expiry,
+        nonce: args.nonce,
+    };
+    verify_ed25519_signature(instructions_sysvar, issuer_signer.key, &grant.to_message())?;
+
+    let vault = GrantVault::load(program_id, vault_account)?;
+    if vault.issuer != *issuer_account.key {
+        msg!("Invalid grant vault account");
+        return Err(GideonError::InvalidGrantVault.into());
+    }
+
+    // Check the voucher itself against the platform limits
+    if args.amount == 0 || args.amount > config.max_voucher_amount {
+        msg!("Voucher amount above limit");
+        return Err(GideonError::VoucherAmountTooLarge.into());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if args.expiry <= now || args.expiry.saturating_sub(now) > config.max_validity_period {
+        msg!("Invalid validity window");
+        return Err(GideonError::InvalidValidityWindow.into());
+    }
+
+    // Mark the nonce as used, which fails if the grant was already redeemed
+    let (nonce_pda, nonce_bump) = Pubkey::find_program_address(
+        &[
+            GrantNonce::SEED_PREFIX.as_bytes(),
+            issuer_account.key.as_ref(),
+            &args.nonce.to_le_bytes(),
+        ],
+        program_id,
+    );
+    if nonce_account.key != &nonce_pda {
+        msg!("Invalid nonce account");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !nonce_account.data_is_empty() {
+        msg!("Nonce already used");
+        return Err(GideonError::NonceAlreadyUsed.into());
+    }
+    create_pda_account(
+        recipient,
+        nonce_account,
+        system_program,
+        GrantNonce::ACCOUNT_SPACE,
+        program_id,
+        &[
+            GrantNonce::SEED_PREFIX.as_bytes(),
+            issuer_account.key.as_ref(),
+            &args.nonce.to_le_bytes(),
+            &[nonce_bump],
+        ],
+    )?;
+    GrantNonce {
+        issuer: *issuer_account.key,
+        nonce: args.nonce,
+        voucher_mint: *mint_account.key,
+        bump: nonce_bump,
+    }
+    .serialize(&mut &mut nonce_account.data.borrow_mut()[..])?;
+
+    // The escrow belongs to the issuer authority, who reclaims it if it expires unused
+    create_escrow(
+        program_id,
+        escrow_account,
+        recipient,
+        system_program,
+        Escrow::new(
+            issuer.authority,
+            *recipient.key,
+            args.amount,
+            0,
+            *mint_account.key,
+            now,
+            args.expiry,
+            0,
+            1,
+            args.amount,
+            None,
+            *issuer_account.key,
+            None,
+            0,
+            0,
+        ),
+    )?;
+
+    // Fund the escrow and fee from the vault, and refund the recipient the escrow rent
+    let escrow_rent = escrow_account.lamports();
+    let fee = config.issuance_fee(args.amount);
+    let total = args
+        .amount
+        .checked_add(escrow_rent)
+        .and_then(|total| total.checked_add(fee))
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    check_vault_balance(vault_account, total)?;
+
+    msg!("Funding escrow from grant vault");
+    transfer_lamports(vault_account, escrow_account, args.amount)?;
+    transfer_lamports(vault_account, recipient, escrow_rent)?;
+    transfer_lamports(vault_account, treasury, fee)?;
+
+    GideonEvent::GrantRedeemed {
+        issuer: *issuer_account.key,
+        signer: *issuer_signer.key,
+        recipient: *recipient.key,
+        voucher_mint: *mint_account.key,
+        escrow: *escrow_account.key,
+        amount: args.amount,
+        nonce: args.nonce,
+        fee,
+    }
+    .emit();
+
+    mint_voucher(
+        program_id,
+        accounts,
+        MintVoucherArgs {
+            title: vault.title,
+            description: vault.description,
+            symbol: vault.symbol,
+            uri: vault.uri,
+            products: Vec::new(),
+        },
+    )
+}
+
+/// Checks the vault can pay out `amount` and stay rent exempt.
+fn check_vault_balance(vault_account: &AccountInfo, amount: u64) -> ProgramResult {
+    let available = vault_account
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(vault_account.data_len()));
+    if amount > available {
+        msg!("Insufficient funds in grant vault");
+        return Err(GideonError::InsufficientFunds.into());
+    }
+
+    Ok(())
+}
@@ -3,14 +3,14 @@ use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
-    program::invoke_signed,
     pubkey::Pubkey,
-    rent::Rent,
-    system_instruction,
-    sysvar::Sysvar,
 };
 
-use crate::state::authority::MintAuthorityPda;
+use crate::{
+    error::GideonError,
+    state::{authority::MintAuthorityPda, issuer::Issuer},
+    utils::create_pda_account,
+};
 
 pub fn init(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
@@ -18,27 +18,40 @@ pub fn init(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let mint_authority = next_account_info(accounts_iter)?;
     let payer = next_account_info(accounts_iter)?;
     let system_program = next_account_info(accounts_iter)?;
+    let issuer_account = next_account_info(accounts_iter)?;
+
+    // Each issuer gets its own mint authority, created by one of its signers
+    let issuer = Issuer::load(program_id, issuer_account)?;
+    if !payer.is_signer || !issuer.is_signer(payer.key) {
+        msg!("Payer must be an issuer signer");
+        return Err(GideonError::UnauthorizedIssuerSigner.into());
+    }
 
-    let (mint_authority_pda, bump) =
-        Pubkey::find_program_address(&[MintAuthorityPda::SEED_PREFIX.as_bytes()], program_id);
-    assert!(&mint_authority_pda.eq(mint_authority.key));
+    let (mint_authority_pda, bump) = Pubkey::find_program_address(
+        &[
+            MintAuthorityPda::SEED_PREFIX.as_bytes(),
+            issuer_account.key.as_ref(),
+        ],
+        program_id,
+    );
+    if mint_authority.key != &mint_authority_pda {
+        msg!("Invalid mint authority account");
+        return Err(GideonError::InvalidMintAuthority.into());
+    }
 
     msg!("Creating mint authority PDA...");
     msg!("Mint Authority: {}", &mint_authority.key);
-    invoke_signed(
-        &system_instruction::create_account(
-            payer.key,
-            mint_authority.key,
-            (Rent::get()?).minimum_balance(MintAuthorityPda::SIZE),
-            MintAuthorityPda::SIZE as u64,
-            program_id,
-        ),
+    create_pda_account(
+        payer,
+        mint_authority,
+        system_program,
+        MintAuthorityPda::SIZE,
+        program_id,
         &[
-            mint_authority.clone(),
-            payer.clone(),
-            system_program.clone(),
+            MintAuthorityPda::SEED_PREFIX.as_bytes(),
+            issuer_account.key.as_ref(),
+            &[bump],
         ],
-        &[&[MintAuthorityPda::SEED_PREFIX.as_bytes(), &[bump]]],
     )?;
 
     let data = MintAuthorityPda { bump };
@@ -0,0 +1,158 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    instructions::config::check_admin,
+    state::{config::Config, issuer::Issuer},
+    utils::create_pda_account,
+};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct IssuerProfileArgs {
+    pub display_name: String,
+    pub logo_uri: String,
+    pub website: String,
+    pub signers: Vec<Pubkey>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct VerifyIssuerArgs {
+    pub verified: bool,
+}
+
+/// Creates the issuer PDA for the signing brand authority. New issuers start unverified.
+pub fn register_issuer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: IssuerProfileArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let issuer_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !authority.is_signer {
+        msg!("Issuer authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    check_profile(&args)?;
+
+    let (issuer_pda, bump) = Pubkey::find_program_address(
+        &[Issuer::SEED_PREFIX.as_bytes(), authority.key.as_ref()],
+        program_id,
+    );
+    if issuer_account.key != &issuer_pda {
+        msg!("Invalid issuer account");
+        return Err(GideonError::InvalidIssuerAccount.into());
+    }
+
+    msg!("Creating issuer PDA...");
+    msg!("Issuer: {}", issuer_account.key);
+    create_pda_account(
+        authority,
+        issuer_account,
+        system_program,
+        Issuer::ACCOUNT_SPACE,
+        program_id,
+        &[
+            Issuer::SEED_PREFIX.as_bytes(),
+            authority.key.as_ref(),
+            &[bump],
+        ],
+    )?;
+
+    let issuer = Issuer {
+        authority: *authority.key,
+        display_name: args.display_name,
+        verified: false,
+        logo_uri: args.logo_uri,
+        website: args.website,
+        signers: args.signers,
+        bump,
+    };
+    issuer.serialize(&mut &mut issuer_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Updates the issuer profile. Any change clears the verified flag until the admin
+/// verifies the issuer again.
+pub fn update_issuer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: IssuerProfileArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let issuer_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+
+    let mut issuer = Issuer::load(program_id, issuer_account)?;
+    if !authority.is_signer || issuer.authority != *authority.key {
+        msg!("Invalid issuer authority");
+        return Err(GideonError::UnauthorizedIssuerSigner.into());
+    }
+    check_profile(&args)?;
+
+    msg!("Updating issuer profile");
+    issuer.display_name = args.display_name;
+    issuer.logo_uri = args.logo_uri;
+    issuer.website = args.website;
+    issuer.signers = args.signers;
+    issuer.verified = false;
+    issuer.serialize(&mut &mut issuer_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+pub fn verify_issuer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: VerifyIssuerArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let issuer_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+
+    let config = Config::load(program_id, config_account)?;
+    check_admin(&config, admin)?;
+
+    let mut issuer = Issuer::load(program_id, issuer_account)?;
+
+    msg!("Setting issuer verified: {}", args.verified);
+    issuer.verified = args.verified;
+    issuer.serialize(&mut &mut issuer_account.data.borrow_mut()[..])?;
+
+    GideonEvent::IssuerVerificationUpdated {
+        issuer: *issuer_account.key,
+        verified: args.verified,
+    }
+    .emit();
+
+    Ok(())
+}
+
+fn check_profile(args: &IssuerProfileArgs) -> ProgramResult {
+    if args.display_name.is_empty()
+        || args.display_name.len() > Issuer::MAX_DISPLAY_NAME_LEN
+        || args.logo_uri.len() > Issuer::MAX_LOGO_URI_LEN
+        || args.website.len() > Issuer::MAX_WEBSITE_LEN
+        || args.signers.len() > Issuer::MAX_SIGNERS
+    {
+        msg!("Invalid issuer profile");
+        return Err(GideonError::InvalidIssuerProfile.into());
+    }
+
+    Ok(())
+}
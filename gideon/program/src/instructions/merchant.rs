@@ -0,0 +1,115 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    state::{issuer::Issuer, merchant::Merchant},
+    utils::{close_account, create_pda_account},
+};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct RegisterMerchantArgs {
+    pub merchant: Pubkey,
+}
+
+/// Registers a merchant allowed to redeem the issuer's product-bound vouchers.
+pub fn register_merchant(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: RegisterMerchantArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let merchant_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let issuer_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    let issuer = Issuer::load(program_id, issuer_account)?;
+    if !authority.is_signer || issuer.authority != *authority.key {
+        msg!("Invalid issuer authority");
+        return Err(GideonError::InvalidIssuerAccount.into());
+    }
+
+    let (merchant_pda, merchant_bump) = Pubkey::find_program_address(
+        &[
+            Merchant::SEED_PREFIX.as_bytes(),
+            issuer_account.key.as_ref(),
+            args.merchant.as_ref(),
+        ],
+        program_id,
+    );
+    if merchant_account.key != &merchant_pda {
+        msg!("Invalid merchant account");
+        return Err(GideonError::MerchantNotRegistered.into());
+    }
+
+    msg!("Creating merchant account");
+    create_pda_account(
+        authority,
+        merchant_account,
+        system_program,
+        Merchant::ACCOUNT_SPACE,
+        program_id,
+        &[
+            Merchant::SEED_PREFIX.as_bytes(),
+            issuer_account.key.as_ref(),
+            args.merchant.as_ref(),
+            &[merchant_bump],
+        ],
+    )?;
+
+    let merchant = Merchant {
+        issuer: *issuer_account.key,
+        merchant: args.merchant,
+        bump: merchant_bump,
+    };
+    merchant.serialize(&mut &mut merchant_account.data.borrow_mut()[..])?;
+
+    GideonEvent::MerchantRegistered {
+        issuer: *issuer_account.key,
+        merchant: args.merchant,
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Removes a merchant's registration, returning its rent to the issuer authority.
+pub fn remove_merchant(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let merchant_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let issuer_account = next_account_info(accounts_iter)?;
+    let merchant = next_account_info(accounts_iter)?;
+
+    let issuer = Issuer::load(program_id, issuer_account)?;
+    if !authority.is_signer || issuer.authority != *authority.key {
+        msg!("Invalid issuer authority");
+        return Err(GideonError::InvalidIssuerAccount.into());
+    }
+    Merchant::check_registered(
+        program_id,
+        merchant_account,
+        issuer_account.key,
+        merchant.key,
+    )?;
+
+    msg!("Closing merchant account");
+    close_account(merchant_account, authority)?;
+
+    GideonEvent::MerchantRemoved {
+        issuer: *issuer_account.key,
+        merchant: *merchant.key,
+    }
+    .emit();
+
+    Ok(())
+}
@@ -0,0 +1,259 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    borsh1::try_from_slice_unchecked,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+use spl_pod::optional_keys::OptionalNonZeroPubkey;
+use spl_token_2022::{
+    extension::{
+        metadata_pointer::MetadataPointer, permanent_delegate::PermanentDelegate,
+        BaseStateWithExtensions, StateWithExtensions,
+    },
+    instruction::{self as token_instruction, AuthorityType},
+    state::Mint,
+};
+use spl_token_metadata_interface::{instruction as metadata_instruction, state::TokenMetadata};
+
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    state::{
+        authority::MintAuthoritySigner,
+        escrow::{Escrow, LegacyEscrow},
+        issuer::Issuer,
+    },
+    utils::check_token_program,
+};
+
+/// Moves a voucher minted under the legacy global mint authority over to its issuer's
+/// mint authority, so the issuer's vouchers can be told apart by authority.
+pub fn migrate_mint_authority(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let authority = next_account_info(accounts_iter)?;
+    let issuer_account = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let legacy_mint_authority = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    // Check the signer acts for the issuer
+    let issuer = Issuer::load(program_id, issuer_account)?;
+    if !authority.is_signer || !issuer.is_signer(authority.key) {
+        msg!("Unauthorized issuer signer");
+        return Err(GideonError::UnauthorizedIssuerSigner.into());
+    }
+    check_token_program(token_program, &[mint_account])?;
+
+    // Check the voucher was issued by this issuer. Legacy escrows don't record their issuer,
+    // so only their common prefix is read and the issuer authority must have paid for them
+    let escrow = try_from_slice_unchecked::<LegacyEscrow>(&escrow_account.data.borrow())?;
+    escrow.check_address(program_id, escrow_account)?;
+    if escrow.voucher_mint != *mint_account.key || escrow.payer != issuer.authority {
+        msg!("Invalid voucher escrow account");
+        return Err(GideonError::InvalidVoucherEscrowAccount.into());
+    }
+
+    // The old authority must be the global PDA, the new one the issuer's PDA
+    let legacy =
+        MintAuthoritySigner::load(program_id, legacy_mint_authority, issuer_account.key, true)?;
+    if legacy.issuer.is_some() {
+        msg!("Voucher already uses an issuer mint authority");
+        return Err(GideonError::InvalidMintAuthority.into());
+    }
+    MintAuthoritySigner::load(program_id, mint_authority, issuer_account.key, false)?;
+    let legacy_seeds = legacy.seeds();
+
+    // Find which mint authorities still point at the global PDA
+    let (metadata_authority, permanent_delegate, metadata_pointer_authority) = {
+        let mint_data = mint_account.try_borrow_data()?;
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+        let metadata_bytes = mint.get_extension_bytes::<TokenMetadata>()?;
+        let metadata = try_from_slice_unchecked::<TokenMetadata>(metadata_bytes)?;
+        (
+            Option::<Pubkey>::from(metadata.update_authority),
+            mint.get_extension::<PermanentDelegate>()
+                .ok()
+                .and_then(|extension| Option::<Pubkey>::from(extension.delegate)),
+            mint.get_extension::<MetadataPointer>()
+                .ok()
+                .and_then(|extension| Option::<Pubkey>::from(extension.authority)),
+        )
+    };
+    if metadata_authority != Some(*legacy_mint_authority.key) {
+        msg!("Voucher metadata not owned by the global mint authority");
+        return Err(GideonError::InvalidMintAuthority.into());
+    }
+
+    msg!("Migrating voucher metadata authority...");
+    invoke_signed(
+        &metadata_instruction::update_authority(
+            token_program.key,
+            mint_account.key,
+            legacy_mint_authority.key,
+            OptionalNonZeroPubkey(*mint_authority.key),
+        ),
+        &[
+            mint_account.clone(),
+            legacy_mint_authority.clone(),
+            token_program.clone(),
+        ],
+        &[&legacy_seeds],
+    )?;
+
+    if permanent_delegate == Some(*legacy_mint_authority.key) {
+        msg!("Migrating voucher permanent delegate...");
+        invoke_signed(
+            &token_instruction::set_authority(
+                token_program.key,
+                mint_account.key,
+                Some(mint_authority.key),
+                AuthorityType::PermanentDelegate,
+                legacy_mint_authority.key,
+                &[legacy_mint_authority.key],
+            )?,
+            &[
+                mint_account.clone(),
+                legacy_mint_authority.clone(),
+                token_program.clone(),
+            ],
+            &[&legacy_seeds],
+        )?;
+    }
+
+    if metadata_pointer_authority == Some(*legacy_mint_authority.key) {
+        msg!("Migrating voucher metadata pointer authority...");
+        invoke_signed(
+            &token_instruction::set_authority(
+                token_program.key,
+                mint_account.key,
+                Some(mint_authority.key),
+                AuthorityType::MetadataPointer,
+                legacy_mint_authority.key,
+                &[legacy_mint_authority.key],
+            )?,
+            &[
+                mint_account.clone(),
+                legacy_mint_authority.clone(),
+                token_program.clone(),
+            ],
+            &[&legacy_seeds],
+        )?;
+    }
+
+    // An escrow migrated before its issuer was known records it now
+    if escrow_account.data_len() != LegacyEscrow::ACCOUNT_SPACE {
+        let mut escrow = Escrow::load(program_id, escrow_account)?;
+        if escrow.issuer == Pubkey::default() {
+            escrow.issuer = *issuer_account.key;
+            escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+        }
+    }
+
+    GideonEvent::VoucherAuthorityMigrated {
+        voucher_mint: *mint_account.key,
+        issuer: *issuer_account.key,
+        mint_authority: *mint_authority.key,
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Converts an escrow still in the legacy layout to the current one, so its voucher can be
+/// redeemed and reclaimed like any other. It becomes a single-use voucher for the whole
+/// amount, expiring when its metadata says.
+///
+/// Anyone can pay for the extra rent, which goes to the payer when the escrow is closed.
+/// Passing the payer's issuer account records it as the voucher's issuer.
+pub fn migrate_escrow(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let funder = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !funder.is_signer {
+        msg!("Funder must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if escrow_account.data_len() != LegacyEscrow::ACCOUNT_SPACE {
+        msg!("Escrow not in the legacy layout");
+        return Err(GideonError::InvalidVoucherEscrowAccount.into());
+    }
+    let legacy = try_from_slice_unchecked::<LegacyEscrow>(&escrow_account.data.borrow())?;
+    legacy.check_address(program_id, escrow_account)?;
+    if legacy.voucher_mint != *mint_account.key || *mint_account.owner != spl_token_2022::id() {
+        msg!("Invalid voucher mint account");
+        return Err(GideonError::InvalidVoucherEscrowAccount.into());
+    }
+
+    // Legacy vouchers only record their expiry in metadata, in milliseconds
+    let valid_until = {
+        let mint_data = mint_account.try_borrow_data()?;
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+        let metadata_bytes = mint.get_extension_bytes::<TokenMetadata>()?;
+        let metadata = try_from_slice_unchecked::<TokenMetadata>(metadata_bytes)?;
+        metadata
+            .additional_metadata
+            .iter()
+            .find(|(key, _)| key == "expiry")
+            .and_then(|(_, value)| value.parse::<i64>().ok())
+            .ok_or(GideonError::InvalidVoucherEscrowAccount)?
+            / 1000
+    };
+
+    let issuer = match next_account_info(accounts_iter) {
+        Ok(issuer_account) => {
+            let issuer = Issuer::load(program_id, issuer_account)?;
+            if issuer.authority != legacy.payer {
+                msg!("Issuer not the voucher payer's");
+                return Err(GideonError::InvalidIssuer.into());
+            }
+            *issuer_account.key
+        }
+        Err(_) => Pubkey::default(),
+    };
+
+    // Keep the escrow rent exempt on top of the value it holds
+    let required = Rent::get()?
+        .minimum_balance(Escrow::ACCOUNT_SPACE)
+        .saturating_add(legacy.amount);
+    let top_up = required.saturating_sub(escrow_account.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(funder.key, escrow_account.key, top_up),
+            &[
+                funder.clone(),
+                escrow_account.clone(),
+                system_program.clone(),
+            ],
+        )?;
+    }
+
+    msg!("Migrating escrow layout");
+    let escrow = legacy.upgrade(valid_until, issuer);
+    escrow_account.realloc(Escrow::ACCOUNT_SPACE, true)?;
+    escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+
+    GideonEvent::EscrowMigrated {
+        voucher_mint: escrow.voucher_mint,
+        escrow: *escrow_account.key,
+        issuer,
+        valid_until,
+    }
+    .emit();
+
+    Ok(())
+}
@@ -0,0 +1,398 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::{
+    extension::StateWithExtensions, instruction as token_instruction, state::Mint,
+};
+
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    instructions::receipt::create_receipt,
+    state::{
+        authority::MintAuthoritySigner,
+        config::Config,
+        escrow::Escrow,
+        milestone::{Milestone, MilestoneSchedule, MilestoneStatus},
+        status::VoucherStatus,
+    },
+    utils::{check_token_program, close_account, create_pda_account, transfer_lamports},
+};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct MilestoneArgs {
+    pub description_hash: [u8; 32],
+    pub amount: u64,
+    pub deadline: i64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct ApproveMilestoneArgs {
+    pub index: u8,
+    /// Partner order or tracking reference for this redemption, defaulting to the voucher's
+    pub external_ref: Option<Vec<u8>>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct ReclaimMilestoneArgs {
+    pub index: u8,
+}
+
+/// Creates the milestone schedule for a newly initialised escrow.
+///
+/// Tranches must add up to the escrowed amount, with deadlines in order and within the
+/// voucher's validity window.
+pub(crate) fn init_milestones<'a>(
+    program_id: &Pubkey,
+    escrow: &Escrow,
+    escrow_account: &AccountInfo<'a>,
+    schedule_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    milestones: Vec<MilestoneArgs>,
+) -> ProgramResult {
+    let now = Clock::get()?.unix_timestamp;
+    let total = milestones
+        .iter()
+        .try_fold(0u64, |total, milestone| total.checked_add(milestone.amount));
+    if milestones.len() > MilestoneSchedule::MAX_MILESTONES
+        || total != Some(escrow.amount)
+        || milestones.iter().any(|milestone| milestone.amount == 0)
+        || milestones
+            .first()
+            .is_some_and(|first| first.deadline <= now)
+        || milestones
+            .last()
+            .is_some_and(|last| last.deadline > escrow.valid_until)
+        || milestones
+            .windows(2)
+            .any(|pair| pair[1].deadline < pair[0].deadline)
+    {
+        msg!("Invalid milestone schedule");
+        return Err(GideonError::InvalidMilestoneSchedule.into());
+    }
+
+    let (schedule_pda, schedule_bump) = Pubkey::find_program_address(
+        &[
+            MilestoneSchedule::SEED_PREFIX.as_bytes(),
+            escrow_account.key.as_ref(),
+        ],
+        program_id,
+    );
+    if schedule_account.key != &schedule_pda {
+        msg!("Invalid milestone schedule account");
+        return Err(GideonError::InvalidMilestoneSchedule.into());
+    }
+
+    let space = MilestoneSchedule::account_space(milestones.len());
+    msg!("Creating milestone schedule account");
+    create_pda_account(
+        payer,
+        schedule_account,
+        system_program,
+        space,
+        program_id,
+        &[
+            MilestoneSchedule::SEED_PREFIX.as_bytes(),
+            escrow_account.key.as_ref(),
+            &[schedule_bump],
+        ],
+    )?;
+
+    let schedule = MilestoneSchedule {
+        escrow: *escrow_account.key,
+        milestones: milestones
+            .into_iter()
+            .map(|milestone| Milestone {
+                description_hash: milestone.description_hash,
+                amount: milestone.amount,
+                deadline: milestone.deadline,
+                status: MilestoneStatus::Pending,
+            })
+            .collect(),
+        bump: schedule_bump,
+    };
+    schedule.serialize(&mut &mut schedule_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Releases the next milestone tranche to the recipient once the payer approves the
+/// deliverable. Earlier milestones must be approved or reclaimed first.
+pub fn approve_milestone(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: ApproveMilestoneArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let schedule_account = next_account_info(accounts_iter)?;
+    let payout = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let receipt_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let token_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    let config = Config::load(program_id, config_account)?;
+    config.check_treasury(treasury)?;
+    Escrow::check_external_ref(args.external_ref.as_deref())?;
+
+    let (mut escrow, mut schedule) =
+        load_milestone_escrow(program_id, payer, escrow_account, schedule_account)?;
+
+    // Check payout account, the recipient unless they chose another address
+    if *payout.key != escrow.payout_address.unwrap_or(escrow.recipient) {
+        msg!("Invalid payout account");
+        return Err(GideonError::InvalidPayoutAccount.into());
+    }
+
+    // Tranches are only released once the voucher's validity window has opened
+    let now = Clock::get()?.unix_timestamp;
+    if now < escrow.valid_from {
+        msg!("Voucher not yet valid");
+        return Err(GideonError::VoucherNotYetValid.into());
+    }
+
+    // Milestones are approved in order
+    let index = args.index as usize;
+    if schedule
+        .milestones
+        .get(index)
+        .map(|milestone| milestone.status)
+        != Some(MilestoneStatus::Pending)
+        || schedule.milestones[..index]
+            .iter()
+            .any(|milestone| milestone.status == MilestoneStatus::Pending)
+    {
+        msg!("Invalid milestone");
+        return Err(GideonError::InvalidMilestone.into());
+    }
+
+    let amount = schedule.milestones[index].amount;
+    schedule.milestones[index].status = MilestoneStatus::Approved;
+    escrow.amount -= amount;
+    let next = if schedule.is_settled() {
+        VoucherStatus::Redeemed
+    } else {
+        VoucherStatus::PartiallyRedeemed
+    };
+    escrow.transition(next, now)?;
+
+    // Pay the tranche, less the protocol fee, like any other redemption
+    msg!("Releasing milestone {}", index);
+    let fee = config.redemption_fee(amount);
+    transfer_lamports(escrow_account, treasury, fee)?;
+    transfer_lamports(escrow_account, payout, amount - fee)?;
+
+    // Each tranche gets its own receipt, numbered by milestone
+    let external_ref = args.external_ref.or_else(|| escrow.external_ref.clone());
+    create_receipt(
+        program_id,
+        receipt_account,
+        payer,
+        system_program,
+        &escrow,
+        &escrow.recipient,
+        payout.key,
+        amount,
+        args.index as u16 + 1,
+        None,
+        external_ref.as_deref(),
+    )?;
+
+    GideonEvent::MilestoneApproved {
+        voucher_mint: escrow.voucher_mint,
+        escrow: *escrow_account.key,
+        index: args.index,
+        recipient: *payout.key,
+        amount,
+        fee,
+    }
+    .emit();
+    GideonEvent::emit_external_ref(escrow.voucher_mint, *escrow_account.key, external_ref);
+
+    settle(
+        program_id,
+        escrow,
+        schedule,
+        escrow_account,
+        schedule_account,
+        payer,
+        mint_account,
+        token_account,
+        mint_authority,
+        token_program,
+    )
+}
+
+/// Returns an unapproved milestone tranche to the payer once its deadline has passed.
+pub fn reclaim_milestone(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: ReclaimMilestoneArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+    let schedule_account = next_account_info(accounts_iter)?;
+    let _config = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let token_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    let (mut escrow, mut schedule) =
+        load_milestone_escrow(program_id, payer, escrow_account, schedule_account)?;
+
+    let index = args.index as usize;
+    let Some(milestone) = schedule
+        .milestones
+        .get_mut(index)
+        .filter(|milestone| milestone.status == MilestoneStatus::Pending)
+    else {
+        msg!("Invalid milestone");
+        return Err(GideonError::InvalidMilestone.into());
+    };
+    let now = Clock::get()?.unix_timestamp;
+    if now <= milestone.deadline {
+        msg!("Milestone not overdue");
+        return Err(GideonError::MilestoneNotOverdue.into());
+    }
+
+    let amount = milestone.amount;
+    milestone.status = MilestoneStatus::Reclaimed;
+    escrow.amount -= amount;
+    let next = if schedule.is_settled() {
+        VoucherStatus::Expired
+    } else {
+        VoucherStatus::PartiallyRedeemed
+    };
+    escrow.transition(next, now)?;
+
+    msg!("Reclaiming milestone {}", index);
+    transfer_lamports(escrow_account, payer, amount)?;
+
+    GideonEvent::MilestoneReclaimed {
+        voucher_mint: escrow.voucher_mint,
+        escrow: *escrow_account.key,
+        index: args.index,
+        amount,
+    }
+    .emit();
+
+    settle(
+        program_id,
+        escrow,
+        schedule,
+        escrow_account,
+        schedule_account,
+        payer,
+        mint_account,
+        token_account,
+        mint_authority,
+        token_program,
+    )
+}
+
+/// Loads a milestone escrow and its schedule, checking `payer` is the signing escrow payer.
+fn load_milestone_escrow(
+    program_id: &Pubkey,
+    payer: &AccountInfo,
+    escrow_account: &AccountInfo,
+    schedule_account: &AccountInfo,
+) -> Result<(Escrow, MilestoneSchedule), ProgramError> {
+    if !payer.is_signer {
+        msg!("Payer must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let escrow = Escrow::load(program_id, escrow_account)?;
+    if escrow.payer != *payer.key {
+        msg!("Invalid payer account");
+        return Err(GideonError::InvalidIssuer.into());
+    }
+    escrow.check_not_disputed()?;
+    escrow.check_not_frozen()?;
+
+    let schedule = MilestoneSchedule::load(program_id, schedule_account, escrow_account.key)?;
+
+    Ok((escrow, schedule))
+}
+
+/// Saves the escrow and schedule, or once every tranche is settled burns the voucher and
+/// closes both to the payer.
+#[allow(clippy::too_many_arguments)]
+fn settle<'a>(
+    program_id: &Pubkey,
+    escrow: Escrow,
+    schedule: MilestoneSchedule,
+    escrow_account: &AccountInfo<'a>,
+    schedule_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    mint_account: &AccountInfo<'a>,
+    token_account: &AccountInfo<'a>,
+    mint_authority: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    if !schedule.is_settled() {
+        escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+        schedule.serialize(&mut &mut schedule_account.data.borrow_mut()[..])?;
+        return Ok(());
+    }
+
+    if escrow.voucher_mint != *mint_account.key {
+        msg!("Invalid mint account");
+        return Err(GideonError::InvalidVoucherEscrowAccount.into());
+    }
+    check_token_program(token_program, &[mint_account])?;
+
+    // Retire the voucher NFT through the program's permanent delegate, unless the holder
+    // already burned it, so a settled voucher can't be presented again
+    let supply = StateWithExtensions::<Mint>::unpack(&mint_account.try_borrow_data()?)?
+        .base
+        .supply;
+    if supply > 0 {
+        msg!("Burning settled voucher");
+        check_token_program(token_program, &[token_account])?;
+        let authority =
+            MintAuthoritySigner::load(program_id, mint_authority, &escrow.issuer, true)?;
+        invoke_signed(
+            &token_instruction::burn_checked(
+                token_program.key,
+                token_account.key,
+                mint_account.key,
+                mint_authority.key,
+                &[mint_authority.key],
+                1,
+                0,
+            )?,
+            &[
+                token_account.clone(),
+                mint_account.clone(),
+                mint_authority.clone(),
+                token_program.clone(),
+            ],
+            &[&authority.seeds()],
+        )?;
+    }
+
+    close_account(schedule_account, payer)?;
+    close_account(escrow_account, payer)?;
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    borsh1::try_from_slice_unchecked,
     entrypoint::ProgramResult,
     msg,
     program::{invoke, invoke_signed},
@@ -17,7 +18,11 @@ use spl_token_2022::{
 };
 use spl_token_metadata_interface::{instruction as metadata_instruction, state::Field};
 
-use crate::state::authority::MintAuthorityPda;
+use crate::{
+    error::GideonError,
+    state::{authority::MintAuthoritySigner, escrow::Escrow},
+    utils::check_token_program,
+};
 
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
 pub struct MintVoucherArgs {
@@ -25,13 +30,54 @@ pub struct MintVoucherArgs {
     pub description: String,
     pub symbol: String,
     pub uri: String,
-    pub expiry: i64,
+    /// SHA-256 hashes of the GTINs or SKUs the voucher can buy, empty for any product
+    pub products: Vec<[u8; 32]>,
+}
+
+impl MintVoucherArgs {
+    pub const MAX_PRODUCTS: usize = 8;
+
+    /// Metadata field listing a product-bound voucher's products, as comma-separated hex
+    pub const PRODUCTS_FIELD: &'static str = "products";
+}
+
+/// Hex encoding of a product hash, as listed in the voucher's metadata.
+pub fn encode_product(product: &[u8; 32]) -> String {
+    product.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Product hashes listed in a voucher's products metadata field.
+pub(crate) fn decode_products(value: &str) -> Option<Vec<[u8; 32]>> {
+    value
+        .split(',')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let mut product = [0; 32];
+            if entry.len() != 64 {
+                return None;
+            }
+            for (byte, pair) in product.iter_mut().zip(entry.as_bytes().chunks(2)) {
+                *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+            }
+            Some(product)
+        })
+        .collect()
 }
 
 pub fn mint_voucher(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     args: MintVoucherArgs,
+) -> ProgramResult {
+    mint_voucher_to(program_id, accounts, args, None)
+}
+
+/// Mints the voucher to `holder`'s associated token account, or to the payer's if `None`.
+pub(crate) fn mint_voucher_to<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    args: MintVoucherArgs,
+    holder: Option<&AccountInfo<'a>>,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
@@ -44,15 +90,37 @@ pub fn mint_voucher(
     let system_program = next_account_info(accounts_iter)?;
     let token_program = next_account_info(accounts_iter)?;
     let _associated_token_program = next_account_info(accounts_iter)?;
+    let holder = holder.unwrap_or(payer);
 
-    let (mint_authority_pda, bump) =
-        Pubkey::find_program_address(&[MintAuthorityPda::SEED_PREFIX.as_bytes()], program_id);
-    assert!(&mint_authority_pda.eq(mint_authority.key));
+    // The mint account is created below, owned by the token program
+    check_token_program(token_program, &[])?;
 
-    let space =
-        ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::MetadataPointer])?;
+    // The validity window and issuer live in the escrow, which has just been initialised
+    let escrow = try_from_slice_unchecked::<Escrow>(&escrow_account.data.borrow())?;
 
-    let meta_data_space = 1000; // TODO: Calculate metadata space
+    // New vouchers are always minted under their issuer's mint authority
+    let authority = MintAuthoritySigner::load(program_id, mint_authority, &escrow.issuer, false)?;
+    let authority_seeds = authority.seeds();
+
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&[
+        ExtensionType::MetadataPointer,
+        ExtensionType::PermanentDelegate,
+    ])?;
+
+    // Product-bound vouchers can only be redeemed by a merchant attesting the product, which
+    // milestone, delivery and fiat redemptions have no way to do
+    if args.products.len() > MintVoucherArgs::MAX_PRODUCTS
+        || !args.products.is_empty()
+            && (escrow.milestone_count > 0
+                || escrow.confirmation_window > 0
+                || escrow.fiat.is_some())
+    {
+        msg!("Invalid product list");
+        return Err(GideonError::InvalidProducts.into());
+    }
+
+    // TODO: Calculate metadata space, on top of 65 bytes per listed product
+    let meta_data_space = 1000 + args.products.len() * 65;
 
     // Get the required rent exemption amount for the account
     let rent_required = Rent::get()?.minimum_balance(space + meta_data_space);
@@ -92,6 +160,18 @@ pub fn mint_voucher(
         ],
     )?;
 
+    // Let the program burn the voucher without the holder signing, e.g. for
+    // merchant-submitted redemptions
+    msg!("Initializing permanent delegate extension...");
+    invoke(
+        &token_instruction::initialize_permanent_delegate(
+            token_program.key,
+            mint_account.key,
+            mint_authority.key,
+        )?,
+        &[mint_account.clone(), token_program.clone()],
+    )?;
+
     // Initialize that account as a Mint
     msg!("Initializing mint account...");
     msg!("Mint: {}", mint_account.key);
@@ -130,7 +210,7 @@ pub fn mint_voucher(
             payer.clone(),
             system_program.clone(),
         ],
-        &[&[MintAuthorityPda::SEED_PREFIX.as_bytes(), &[bump]]],
+        &[&authority_seeds],
     )?;
 
     invoke_signed(
@@ -147,7 +227,7 @@ pub fn mint_voucher(
             mint_authority.clone(),
             token_program.clone(),
         ],
-        &[&[MintAuthorityPda::SEED_PREFIX.as_bytes(), &[bump]]],
+        &[&authority_seeds],
     )?;
 
     invoke_signed(
@@ -156,7 +236,8 @@ pub fn mint_voucher(
             mint_account.key,
             mint_authority.key,
             Field::Key("expiry".to_string()),
-            args.expiry.to_string(),
+            // Milliseconds, for display by wallets and the frontend only
+            escrow.valid_until.saturating_mul(1000).to_string(),
         ),
         &[
             escrow_account.clone(),
@@ -164,9 +245,46 @@ pub fn mint_voucher(
             mint_authority.clone(),
             token_program.clone(),
         ],
-        &[&[MintAuthorityPda::SEED_PREFIX.as_bytes(), &[bump]]],
+        &[&authority_seeds],
     )?;
 
+    invoke_signed(
+        &metadata_instruction::update_field(
+            token_program.key,
+            mint_account.key,
+            mint_authority.key,
+            Field::Key("issuer".to_string()),
+            escrow.issuer.to_string(),
+        ),
+        &[
+            escrow_account.clone(),
+            mint_account.clone(),
+            mint_authority.clone(),
+            token_program.clone(),
+        ],
+        &[&authority_seeds],
+    )?;
+
+    if !args.products.is_empty() {
+        let products: Vec<String> = args.products.iter().map(encode_product).collect();
+        invoke_signed(
+            &metadata_instruction::update_field(
+                token_program.key,
+                mint_account.key,
+                mint_authority.key,
+                Field::Key(MintVoucherArgs::PRODUCTS_FIELD.to_string()),
+                products.join(","),
+            ),
+            &[
+                escrow_account.clone(),
+                mint_account.clone(),
+                mint_authority.clone(),
+                token_program.clone(),
+            ],
+            &[&authority_seeds],
+        )?;
+    }
+
     msg!("Token mint created successfully.");
 
     // First create account for the Mint
@@ -175,14 +293,14 @@ pub fn mint_voucher(
         invoke(
             &associated_token_account_instruction::create_associated_token_account(
                 payer.key,
-                payer.key,
+                holder.key,
                 mint_account.key,
                 token_program.key,
             ),
             &[
                 payer.clone(),
                 associated_token_account.clone(),
-                payer.clone(),
+                holder.clone(),
                 mint_account.clone(),
                 system_program.clone(),
                 token_program.clone(),
@@ -210,7 +328,7 @@ pub fn mint_voucher(
             associated_token_account.clone(),
             token_program.clone(),
         ],
-        &[&[MintAuthorityPda::SEED_PREFIX.as_bytes(), &[bump]]],
+        &[&authority_seeds],
     )?;
 
     msg!("Disabling future minting of this NFT...");
@@ -228,25 +346,29 @@ pub fn mint_voucher(
             mint_authority.clone(),
             token_program.clone(),
         ],
-        &[&[MintAuthorityPda::SEED_PREFIX.as_bytes(), &[bump]]],
-    )?;
-    invoke_signed(
-        &token_instruction::set_authority(
-            token_program.key,
-            mint_account.key,
-            None,
-            token_instruction::AuthorityType::FreezeAccount,
-            mint_authority.key,
-            &[mint_authority.key],
-        )?,
-        &[
-            mint_account.clone(),
-            mint_authority.clone(),
-            token_program.clone(),
-        ],
-        &[&[MintAuthorityPda::SEED_PREFIX.as_bytes(), &[bump]]],
+        &[&authority_seeds],
     )?;
 
+    // Freezable vouchers keep the program as freeze authority, for the issuer to use
+    if !escrow.freezable {
+        invoke_signed(
+            &token_instruction::set_authority(
+                token_program.key,
+                mint_account.key,
+                None,
+                token_instruction::AuthorityType::FreezeAccount,
+                mint_authority.key,
+                &[mint_authority.key],
+            )?,
+            &[
+                mint_account.clone(),
+                mint_authority.clone(),
+                token_program.clone(),
+            ],
+            &[&authority_seeds],
+        )?;
+    }
+
     msg!("NFT minted successfully.");
 
     Ok(())
@@ -1,6 +1,22 @@
+pub mod authorized;
 pub mod burn;
+pub mod campaign;
+pub mod code;
+pub mod config;
 pub mod create;
+pub mod delivery;
+pub mod dispute;
 pub mod escrow;
 pub mod expired;
+pub mod freeze;
+pub mod grant;
 pub mod init_authority;
+pub mod issuer;
+pub mod merchant;
+pub mod migrate;
+pub mod milestone;
 pub mod mint;
+pub mod payout;
+pub mod receipt;
+pub mod recover;
+pub mod refund;
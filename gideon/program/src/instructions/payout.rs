@@ -0,0 +1,46 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{error::GideonError, state::escrow::Escrow};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct SetPayoutAddressArgs {
+    pub payout_address: Option<Pubkey>,
+}
+
+/// Lets the voucher recipient send redemption payouts to another address, e.g. when a
+/// delegate redeems on their behalf. `None` pays the recipient again.
+pub fn set_payout_address(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: SetPayoutAddressArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let recipient = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+
+    if !recipient.is_signer {
+        msg!("Recipient must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut escrow = Escrow::load(program_id, escrow_account)?;
+    if escrow.recipient != *recipient.key {
+        msg!("Invalid recipient account");
+        return Err(GideonError::InvalidRecipientAccount.into());
+    }
+    escrow.check_not_disputed()?;
+
+    msg!("Setting payout address");
+    escrow.payout_address = args.payout_address;
+    escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
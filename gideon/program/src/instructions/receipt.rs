@@ -0,0 +1,78 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
+    program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
+};
+
+use crate::{
+    state::{escrow::Escrow, receipt::RedemptionReceipt},
+    utils::create_pda_account,
+};
+
+/// Creates the receipt PDA for redemption number `redemption` of `escrow`'s voucher, with
+/// `funder` paying the rent. `external_ref` must have been checked against the maximum.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_receipt<'a>(
+    program_id: &Pubkey,
+    receipt_account: &AccountInfo<'a>,
+    funder: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    escrow: &Escrow,
+    holder: &Pubkey,
+    merchant: &Pubkey,
+    amount: u64,
+    redemption: u16,
+    product: Option<[u8; 32]>,
+    external_ref: Option<&[u8]>,
+) -> ProgramResult {
+    let redemption_bytes = redemption.to_le_bytes();
+    let (receipt_pda, receipt_bump) = Pubkey::find_program_address(
+        &[
+            RedemptionReceipt::SEED_PREFIX.as_bytes(),
+            escrow.voucher_mint.as_ref(),
+            &redemption_bytes,
+        ],
+        program_id,
+    );
+    if receipt_account.key != &receipt_pda {
+        msg!("Invalid receipt account");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    msg!("Creating redemption receipt");
+    create_pda_account(
+        funder,
+        receipt_account,
+        system_program,
+        RedemptionReceipt::ACCOUNT_SPACE,
+        program_id,
+        &[
+            RedemptionReceipt::SEED_PREFIX.as_bytes(),
+            escrow.voucher_mint.as_ref(),
+            &redemption_bytes,
+            &[receipt_bump],
+        ],
+    )?;
+
+    let clock = Clock::get()?;
+    let receipt = RedemptionReceipt {
+        voucher_mint: escrow.voucher_mint,
+        issuer: escrow.issuer,
+        holder: *holder,
+        merchant: *merchant,
+        amount,
+        timestamp: clock.unix_timestamp,
+        slot: clock.slot,
+        redemption,
+        product: product.unwrap_or_default(),
+        external_ref: external_ref.map_or([0; 32], |external_ref| {
+            let mut padded = [0; 32];
+            padded[..external_ref.len()].copy_from_slice(external_ref);
+            padded
+        }),
+        bump: receipt_bump,
+    };
+    receipt.serialize(&mut &mut receipt_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    borsh1::try_from_slice_unchecked,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::{
+    extension::{BaseStateWithExtensions, StateWithExtensions},
+    instruction as token_instruction,
+    state::{Account, Mint},
+};
+use spl_token_metadata_interface::state::TokenMetadata;
+
+use crate::{
+    error::GideonError,
+    events::GideonEvent,
+    instructions::mint::{decode_products, mint_voucher_to, MintVoucherArgs},
+    state::{
+        authority::MintAuthoritySigner, config::Config, escrow::Escrow, issuer::Issuer,
+        status::VoucherStatus,
+    },
+    utils::check_token_program,
+};
+
+/// Replaces a voucher whose holder lost their wallet. The old voucher is burned through the
+/// program's permanent delegate and a replacement is minted to the new wallet, backed by
+/// the same escrow, so no funds move. The escrow's `recipient` and `voucher_mint` then name
+/// the new holder and mint, and its `original_seeds` the keys its address was derived from.
+///
+/// Needs a signer of the voucher's issuer, and the admin too when the config requires it,
+/// which it does unless the admin turns it off. The new wallet can't be the voucher's payer
+/// or one of the issuer's signers.
+/// Vouchers minted under the global mint authority must be migrated first.
+pub fn recover_voucher(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let escrow_account = next_account_info(accounts_iter)?;
+    let authority = next_account_info(accounts_iter)?;
+    let mint_account = next_account_info(accounts_iter)?;
+    let mint_authority = next_account_info(accounts_iter)?;
+    let _associated_token_account = next_account_info(accounts_iter)?;
+    let _rent = next_account_info(accounts_iter)?;
+    let _system_program = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let _associated_token_program = next_account_info(accounts_iter)?;
+    let new_holder = next_account_info(accounts_iter)?;
+    let old_mint_account = next_account_info(accounts_iter)?;
+    let old_token_account = next_account_info(accounts_iter)?;
+    let issuer_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    if !authority.is_signer {
+        msg!("Authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    check_token_program(token_program, &[old_mint_account, old_token_account])?;
+
+    let config = Config::load(program_id, config_account)?;
+    if config.recovery_requires_admin {
+        let admin = next_account_info(accounts_iter)?;
+        if *admin.key != config.admin || !admin.is_signer {
+            msg!("Recovery requires the admin's signature");
+            return Err(GideonError::InvalidAdmin.into());
+        }
+    }
+
+    let mut escrow = Escrow::load(program_id, escrow_account)?;
+    let issuer = Issuer::load(program_id, issuer_account)?;
+    if escrow.issuer != *issuer_account.key || !issuer.is_signer(authority.key) {
+        msg!("Unauthorized issuer signer");
+        return Err(GideonError::UnauthorizedIssuerSigner.into());
+    }
+    if escrow.voucher_mint != *old_mint_account.key {
+        msg!("Invalid voucher mint account");
+        return Err(GideonError::InvalidVoucherEscrowAccount.into());
+    }
+
+    // Recovery must not turn into a clawback by whoever funded or issued the voucher
+    if *new_holder.key == escrow.payer || issuer.is_signer(new_holder.key) {
+        msg!("Voucher can't be recovered to its payer or issuer");
+        return Err(GideonError::InvalidRecoveryHolder.into());
+    }
+    escrow.check_not_disputed()?;
+    if escrow.fulfilment.is_some() {
+        msg!("Fulfilment pending");
+        return Err(GideonError::FulfilmentPending.into());
+    }
+
+    // A voucher frozen after being reported lost comes back to life in the new wallet
+    let now = Clock::get()?.unix_timestamp;
+    let frozen = escrow.status == VoucherStatus::Frozen;
+    if frozen {
+        escrow.transition(escrow.live_status(now), now)?;
+    }
+
+    // The replacement carries over the old voucher's name, symbol, uri and products
+    let metadata = {
+        let mint_data = old_mint_account.try_borrow_data()?;
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+        let metadata_bytes = mint.get_extension_bytes::<TokenMetadata>()?;
+        try_from_slice_unchecked::<TokenMetadata>(metadata_bytes)?
+    };
+    let products = metadata
+        .additional_metadata
+        .iter()
+        .find(|(key, _)| key == MintVoucherArgs::PRODUCTS_FIELD)
+        .map_or(Some(Vec::new()), |(_, value)| decode_products(value))
+        .ok_or(GideonError::InvalidProducts)?;
+    let old_holder = {
+        let token_data = old_token_account.try_borrow_data()?;
+        let voucher = StateWithExtensions::<Account>::unpack(&token_data)?;
+        if voucher.base.mint != *old_mint_account.key || voucher.base.amount != 1 {
+            msg!("Invalid voucher holder");
+            return Err(GideonError::InvalidVoucherHolder.into());
+        }
+        voucher.base.owner
+    };
+
+    // Burn the old voucher through the program's permanent delegate, thawing it first
+    msg!("Burning lost voucher");
+    let signer = MintAuthoritySigner::load(program_id, mint_authority, &escrow.issuer, false)?;
+    if frozen {
+        invoke_signed(
+            &token_instruction::thaw_account(
+                token_program.key,
+                old_token_account.key,
+                old_mint_account.key,
+                mint_authority.key,
+                &[mint_authority.key],
+            )?,
+            &[
+                old_token_account.clone(),
+                old_mint_account.clone(),
+                mint_authority.clone(),
+                token_program.clone(),
+            ],
+            &[&signer.seeds()],
+        )?;
+    }
+    invoke_signed(
+        &token_instruction::burn_checked(
+            token_program.key,
+            old_token_account.key,
+            old_mint_account.key,
+            mint_authority.key,
+            &[mint_authority.key],
+            1,
+            0,
+        )?,
+        &[
+            old_token_account.clone(),
+            old_mint_account.clone(),
+            mint_authority.clone(),
+            token_program.clone(),
+        ],
+        &[&signer.seeds()],
+    )?;
+
+    // Re-point the escrow at the replacement and its new holder, keeping the keys its address
+    // was derived from. The recipient's payout address goes with the lost wallet
+    escrow.original_seeds = Some(escrow.seeds());
+    escrow.voucher_mint = *mint_account.key;
+    escrow.recipient = *new_holder.key;
+    escrow.payout_address = None;
+    escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+
+    GideonEvent::VoucherRecovered {
+        escrow: *escrow_account.key,
+        old_mint: *old_mint_account.key,
+        new_mint: *mint_account.key,
+        old_holder,
+        new_holder: *new_holder.key,
+        authority: *authority.key,
+    }
+    .emit();
+
+    mint_voucher_to(
+        program_id,
+        accounts,
+        MintVoucherArgs {
+            title: metadata.name,
+            description: String::new(),
+            symbol: metadata.symbol,
+            uri: metadata.uri,
+            products,
+        },
+        Some(new_holder),
+    )
+}
@@ -0,0 +1,45 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{error::GideonError, state::escrow::Escrow};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct SetRefundAddressArgs {
+    pub refund_address: Option<Pubkey>,
+}
+
+/// Lets the voucher payer have an expired escrow refunded to another address, e.g. a
+/// treasury wallet. `None` refunds the payer again.
+pub fn set_refund_address(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: SetRefundAddressArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+    let escrow_account = next_account_info(accounts_iter)?;
+
+    if !payer.is_signer {
+        msg!("Payer must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut escrow = Escrow::load(program_id, escrow_account)?;
+    if escrow.payer != *payer.key {
+        msg!("Invalid payer account");
+        return Err(GideonError::InvalidIssuer.into());
+    }
+
+    msg!("Setting refund address");
+    escrow.refund_address = args.refund_address;
+    escrow.serialize(&mut &mut escrow_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
@@ -1,5 +1,9 @@
 pub mod error;
+pub mod events;
 pub mod instruction;
 pub mod instructions;
+pub mod merkle;
+pub mod oracle;
 pub mod processor;
 pub mod state;
+pub mod utils;
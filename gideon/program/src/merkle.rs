@@ -0,0 +1,139 @@
+//! Merkle tree hashing for airdrop campaigns, shared with the off-chain tree builder.
+//!
+//! Leaves and nodes are domain-separated so a node can never be passed off as a leaf, and
+//! pairs are sorted before hashing so proofs don't need to record left or right.
+
+use solana_program::{hash::hashv, pubkey::Pubkey};
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Hash of one airdrop entry, the voucher `recipient` can claim at position `index`.
+pub fn leaf_hash(index: u32, recipient: &Pubkey, amount: u64, expiry: i64) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        &index.to_le_bytes(),
+        recipient.as_ref(),
+        &amount.to_le_bytes(),
+        &expiry.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Hash of the parent of two nodes.
+pub fn node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Whether `proof` links `leaf` to `root`.
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| node_hash(&node, sibling))
+        == *root
+}
+
+/// Merkle tree over a campaign's leaves, built off-chain to get its root and claim proofs.
+pub struct MerkleTree {
+    /// Each level pairs up the nodes below it, carrying an odd node up unchanged
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    /// Builds the tree over `leaves`, in claim index order. `None` if there are no leaves.
+    pub fn new(leaves: Vec<[u8; 32]>) -> Option<Self> {
+        if leaves.is_empty() {
+            return None;
+        }
+
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    [node] => *node,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(level);
+        }
+
+        Some(Self { levels })
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels.last().unwrap()[0]
+    }
+
+    /// Proof linking the leaf at `index` to the root, for `verify`.
+    pub fn proof(&self, index: usize) -> Vec<[u8; 32]> {
+        self.levels[..self.levels.len() - 1]
+            .iter()
+            .enumerate()
+            .filter_map(|(depth, level)| level.get((index >> depth) ^ 1).copied())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPIRY: i64 = 1_700_000_000;
+
+    fn recipients(count: usize) -> Vec<Pubkey> {
+        (0..count).map(|_| Pubkey::new_unique()).collect()
+    }
+
+    fn leaves(recipients: &[Pubkey]) -> Vec<[u8; 32]> {
+        recipients
+            .iter()
+            .enumerate()
+            .map(|(index, recipient)| leaf_hash(index as u32, recipient, 1_000, EXPIRY))
+            .collect()
+    }
+
+    #[test]
+    fn every_proof_verifies() {
+        for count in [1, 2, 3, 5] {
+            let leaves = leaves(&recipients(count));
+            let tree = MerkleTree::new(leaves.clone()).unwrap();
+
+            for (index, leaf) in leaves.into_iter().enumerate() {
+                assert!(
+                    verify(&tree.proof(index), &tree.root(), leaf),
+                    "leaf {} of {}",
+                    index,
+                    count
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_a_wrong_index() {
+        let recipients = recipients(5);
+        let tree = MerkleTree::new(leaves(&recipients)).unwrap();
+
+        let leaf = leaf_hash(3, &recipients[2], 1_000, EXPIRY);
+        assert!(!verify(&tree.proof(2), &tree.root(), leaf));
+    }
+
+    #[test]
+    fn rejects_a_tampered_amount() {
+        let recipients = recipients(5);
+        let tree = MerkleTree::new(leaves(&recipients)).unwrap();
+
+        let leaf = leaf_hash(2, &recipients[2], 1_001, EXPIRY);
+        assert!(!verify(&tree.proof(2), &tree.root(), leaf));
+    }
+
+    #[test]
+    fn needs_a_leaf() {
+        assert!(MerkleTree::new(Vec::new()).is_none());
+    }
+}
@@ -0,0 +1,289 @@
+//! Price feed accounts for fiat vouchers, read using the Pyth price account layout.
+//!
+//! Only the aggregate price, its confidence, exponent, status and publish time are read. A
+//! feed must quote the price of one SOL in the voucher's currency, e.g. SOL/ZAR.
+
+use solana_program::{
+    account_info::AccountInfo, msg, native_token::LAMPORTS_PER_SOL, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::GideonError;
+
+const MAGIC: u32 = 0xa1b2c3d4;
+const ACCOUNT_TYPE_PRICE: u32 = 3;
+const STATUS_TRADING: u32 = 1;
+
+const MAGIC_OFFSET: usize = 0;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPONENT_OFFSET: usize = 20;
+const TIMESTAMP_OFFSET: usize = 96;
+const PRICE_OFFSET: usize = 208;
+const CONFIDENCE_OFFSET: usize = 216;
+const STATUS_OFFSET: usize = 224;
+
+/// Size of a price account up to the last field read
+pub const PRICE_ACCOUNT_SIZE: usize = 240;
+
+/// Oldest price accepted, measured from its publish time (60 seconds)
+pub const MAX_PRICE_AGE: i64 = 60;
+
+/// Widest confidence interval accepted, relative to the price (2%)
+pub const MAX_CONFIDENCE_BPS: u64 = 200;
+
+/// Largest exponent, either way, a feed can quote its price with
+const MAX_EXPONENT: i32 = 18;
+
+/// Aggregate price of one SOL, `price * 10^expo` units of the quoted currency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Price {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl Price {
+    /// Loads the current price from `price_feed`, which must be owned by `oracle_program`,
+    /// failing if it is stale at `now` or its confidence interval is too wide to settle
+    /// against.
+    pub fn load(
+        price_feed: &AccountInfo,
+        oracle_program: &Pubkey,
+        now: i64,
+    ) -> Result<Self, ProgramError> {
+        // Anyone can create an account with this layout, only the oracle's are trusted
+        if *oracle_program == Pubkey::default() || price_feed.owner != oracle_program {
+            msg!("Price feed not owned by the oracle program");
+            return Err(GideonError::InvalidPriceFeed.into());
+        }
+
+        let price = Self::unpack(&price_feed.try_borrow_data()?)?;
+
+        if now.saturating_sub(price.publish_time) > MAX_PRICE_AGE {
+            msg!("Price feed stale");
+            return Err(GideonError::StalePrice.into());
+        }
+        if price.conf as u128 * 10_000 > price.price as u128 * MAX_CONFIDENCE_BPS as u128 {
+            msg!("Price confidence too wide");
+            return Err(GideonError::PriceUncertain.into());
+        }
+
+        Ok(price)
+    }
+
+    fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < PRICE_ACCOUNT_SIZE
+            || read_u32(data, MAGIC_OFFSET) != MAGIC
+            || read_u32(data, ACCOUNT_TYPE_OFFSET) != ACCOUNT_TYPE_PRICE
+        {
+            msg!("Invalid price feed account");
+            return Err(GideonError::InvalidPriceFeed.into());
+        }
+
+        let price = Self {
+            price: read_u64(data, PRICE_OFFSET) as i64,
+            conf: read_u64(data, CONFIDENCE_OFFSET),
+            expo: read_u32(data, EXPONENT_OFFSET) as i32,
+            publish_time: read_u64(data, TIMESTAMP_OFFSET) as i64,
+        };
+        if read_u32(data, STATUS_OFFSET) != STATUS_TRADING
+            || price.price <= 0
+            || price.expo.abs() > MAX_EXPONENT
+        {
+            msg!("Price feed not trading");
+            return Err(GideonError::InvalidPriceFeed.into());
+        }
+
+        Ok(price)
+    }
+
+    /// Lamports worth `face_value` minor units of the quoted currency, which has `decimals`
+    /// minor unit decimals, rounded down.
+    pub fn lamports_for(&self, face_value: u64, decimals: u8) -> Result<u64, ProgramError> {
+        let scale = |exponent: u32| 10u128.checked_pow(exponent);
+
+        let mut numerator = (face_value as u128).checked_mul(LAMPORTS_PER_SOL as u128);
+        let mut denominator =
+            scale(decimals as u32).and_then(|d| d.checked_mul(self.price as u128));
+        if self.expo < 0 {
+            numerator = numerator
+                .zip(scale(self.expo.unsigned_abs()))
+                .and_then(|(n, s)| n.checked_mul(s));
+        } else {
+            denominator = denominator
+                .zip(scale(self.expo as u32))
+                .and_then(|(d, s)| d.checked_mul(s));
+        }
+
+        numerator
+            .zip(denominator)
+            .and_then(|(n, d)| u64::try_from(n / d).ok())
+            .ok_or(ProgramError::ArithmeticOverflow)
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    /// SOL/ZAR at 2 500.00, quoted with 8 decimals
+    const PRICE: i64 = 250_000_000_000;
+
+    /// A price account laid out like a Pyth one, quoting `price` with 8 decimals.
+    fn mock_price_account(price: i64, conf: u64, publish_time: i64) -> Vec<u8> {
+        let mut data = vec![0; PRICE_ACCOUNT_SIZE];
+        data[MAGIC_OFFSET..][..4].copy_from_slice(&MAGIC.to_le_bytes());
+        data[ACCOUNT_TYPE_OFFSET..][..4].copy_from_slice(&ACCOUNT_TYPE_PRICE.to_le_bytes());
+        data[EXPONENT_OFFSET..][..4].copy_from_slice(&(-8i32).to_le_bytes());
+        data[TIMESTAMP_OFFSET..][..8].copy_from_slice(&publish_time.to_le_bytes());
+        data[PRICE_OFFSET..][..8].copy_from_slice(&price.to_le_bytes());
+        data[CONFIDENCE_OFFSET..][..8].copy_from_slice(&conf.to_le_bytes());
+        data[STATUS_OFFSET..][..4].copy_from_slice(&STATUS_TRADING.to_le_bytes());
+        data
+    }
+
+    /// Oracle program the tests' config allows
+    const ORACLE_PROGRAM: Pubkey = Pubkey::new_from_array([7; 32]);
+
+    fn load(data: Vec<u8>) -> Result<Price, ProgramError> {
+        load_owned_by(data, ORACLE_PROGRAM, &ORACLE_PROGRAM)
+    }
+
+    fn load_owned_by(
+        mut data: Vec<u8>,
+        owner: Pubkey,
+        oracle_program: &Pubkey,
+    ) -> Result<Price, ProgramError> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        Price::load(&account, oracle_program, NOW)
+    }
+
+    fn error(err: GideonError) -> Result<Price, ProgramError> {
+        Err(err.into())
+    }
+
+    #[test]
+    fn loads_a_fresh_price() {
+        let price = load(mock_price_account(PRICE, 100_000_000, NOW - 5)).unwrap();
+        assert_eq!(
+            price,
+            Price {
+                price: PRICE,
+                conf: 100_000_000,
+                expo: -8,
+                publish_time: NOW - 5,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_stale_prices() {
+        let data = mock_price_account(PRICE, 0, NOW - MAX_PRICE_AGE);
+        assert!(load(data).is_ok());
+
+        let data = mock_price_account(PRICE, 0, NOW - MAX_PRICE_AGE - 1);
+        assert_eq!(load(data), error(GideonError::StalePrice));
+    }
+
+    #[test]
+    fn rejects_wide_confidence_intervals() {
+        // 2% of 2 500.00 is 50.00
+        let data = mock_price_account(PRICE, 5_000_000_000, NOW);
+        assert!(load(data).is_ok());
+
+        let data = mock_price_account(PRICE, 5_000_000_001, NOW);
+        assert_eq!(load(data), error(GideonError::PriceUncertain));
+    }
+
+    #[test]
+    fn rejects_other_accounts() {
+        let mut data = mock_price_account(PRICE, 0, NOW);
+        data.truncate(PRICE_ACCOUNT_SIZE - 1);
+        assert_eq!(load(data), error(GideonError::InvalidPriceFeed));
+
+        let mut data = mock_price_account(PRICE, 0, NOW);
+        data[MAGIC_OFFSET] ^= 1;
+        assert_eq!(load(data), error(GideonError::InvalidPriceFeed));
+
+        let mut data = mock_price_account(PRICE, 0, NOW);
+        data[ACCOUNT_TYPE_OFFSET] = 2;
+        assert_eq!(load(data), error(GideonError::InvalidPriceFeed));
+
+        let mut data = mock_price_account(PRICE, 0, NOW);
+        data[STATUS_OFFSET] = 0;
+        assert_eq!(load(data), error(GideonError::InvalidPriceFeed));
+
+        let data = mock_price_account(-1, 0, NOW);
+        assert_eq!(load(data), error(GideonError::InvalidPriceFeed));
+    }
+
+    #[test]
+    fn rejects_feeds_not_owned_by_the_oracle_program() {
+        let data = mock_price_account(PRICE, 0, NOW);
+        assert_eq!(
+            load_owned_by(data, Pubkey::new_unique(), &ORACLE_PROGRAM),
+            error(GideonError::InvalidPriceFeed)
+        );
+
+        // No oracle program configured
+        let data = mock_price_account(PRICE, 0, NOW);
+        assert_eq!(
+            load_owned_by(data, Pubkey::default(), &Pubkey::default()),
+            error(GideonError::InvalidPriceFeed)
+        );
+    }
+
+    #[test]
+    fn converts_face_value_to_lamports() {
+        let price = load(mock_price_account(PRICE, 0, NOW)).unwrap();
+
+        // ZAR 1 500.00 at 2 500.00 per SOL is 0.6 SOL
+        assert_eq!(price.lamports_for(150_000, 2), Ok(600_000_000));
+
+        let price = Price {
+            price: 25,
+            expo: 2,
+            ..price
+        };
+        assert_eq!(price.lamports_for(150_000, 2), Ok(600_000_000));
+
+        // ZAR 0.01 at 3.00 per SOL, rounded down to the lamport
+        let price = Price {
+            price: 300,
+            expo: -2,
+            ..price
+        };
+        assert_eq!(price.lamports_for(1, 2), Ok(3_333_333));
+    }
+
+    #[test]
+    fn conversion_overflow_is_an_error() {
+        let price = load(mock_price_account(1, 0, NOW)).unwrap();
+        assert_eq!(
+            price.lamports_for(u64::MAX, 0),
+            Err(ProgramError::ArithmeticOverflow)
+        );
+    }
+}
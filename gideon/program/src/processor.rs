@@ -1,19 +1,40 @@
 use borsh::BorshDeserialize;
 use solana_program::{
-    account_info::AccountInfo, declare_id, entrypoint, entrypoint::ProgramResult, pubkey::Pubkey,
+    account_info::AccountInfo, declare_id, entrypoint::ProgramResult, msg, pubkey::Pubkey,
 };
 
 use crate::{
+    error::GideonError,
     instruction::GideonInstruction,
     instructions::{
-        burn::burn_voucher_release_escrow, escrow::init_escrow, expired::release_expired_escrow,
-        init_authority::init, mint::mint_voucher,
+        authorized::redeem_with_authorization,
+        burn::{burn_voucher_release_escrow, redeem_voucher},
+        campaign::{claim_voucher, close_campaign, create_campaign},
+        code::redeem_with_code,
+        config::{accept_admin, init_config, set_pause, transfer_admin, update_config},
+        delivery::{claim_fulfilment, reject_fulfilment, release_fulfilment},
+        dispute::{lapse_dispute, open_dispute, resolve_dispute},
+        escrow::init_escrow,
+        expired::release_expired_escrow,
+        freeze::{freeze_voucher, thaw_voucher},
+        grant::{init_grant_vault, redeem_grant, withdraw_grant_vault},
+        init_authority::init,
+        issuer::{register_issuer, update_issuer, verify_issuer},
+        merchant::{register_merchant, remove_merchant},
+        migrate::{migrate_escrow, migrate_mint_authority},
+        milestone::{approve_milestone, reclaim_milestone},
+        mint::mint_voucher,
+        payout::set_payout_address,
+        recover::recover_voucher,
+        refund::set_refund_address,
     },
+    state::config::Config,
 };
 
 // declare and export the program's entrypoint
 declare_id!("gidsaNxwQbr6pyLDaqVn4pPwAypkjwFNZQvvKBJ1Rbi");
-entrypoint!(process_instruction);
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
 
 // program entrypoint's implementation
 pub fn process_instruction(
@@ -23,6 +44,21 @@ pub fn process_instruction(
 ) -> ProgramResult {
     let instruction = GideonInstruction::try_from_slice(instruction_data)?;
 
+    // Stop paused instructions before they touch any accounts
+    if let Some(pause_flag) = instruction.pause_flag() {
+        let (config_pda, _) =
+            Pubkey::find_program_address(&[Config::SEED_PREFIX.as_bytes()], program_id);
+        let config_account = accounts
+            .iter()
+            .find(|account| account.key == &config_pda)
+            .ok_or(GideonError::InvalidConfigAccount)?;
+        let config = Config::load(program_id, config_account)?;
+        if config.paused & pause_flag != 0 {
+            msg!("Program paused");
+            return Err(GideonError::ProgramPaused.into());
+        }
+    }
+
     match instruction {
         GideonInstruction::InitMintAuthority => init(program_id, accounts),
         GideonInstruction::InitEscrowAndMintVoucher(escrow_args, mint_args) => {
@@ -35,8 +71,46 @@ pub fn process_instruction(
             Ok(())
         }
         GideonInstruction::ReleaseExpiredEscrow => {
-            release_expired_escrow(accounts)?;
+            release_expired_escrow(program_id, accounts)?;
             Ok({})
         }
+        GideonInstruction::Redeem => redeem_voucher(program_id, accounts),
+        GideonInstruction::RedeemWithAuthorization(args) => {
+            redeem_with_authorization(program_id, accounts, args)
+        }
+        GideonInstruction::RedeemWithCode(args) => redeem_with_code(program_id, accounts, args),
+        GideonInstruction::SetPayoutAddress(args) => set_payout_address(program_id, accounts, args),
+        GideonInstruction::InitConfig(args) => init_config(program_id, accounts, args),
+        GideonInstruction::UpdateConfig(args) => update_config(program_id, accounts, args),
+        GideonInstruction::TransferAdmin(args) => transfer_admin(program_id, accounts, args),
+        GideonInstruction::AcceptAdmin => accept_admin(program_id, accounts),
+        GideonInstruction::SetPause(args) => set_pause(program_id, accounts, args),
+        GideonInstruction::RegisterIssuer(args) => register_issuer(program_id, accounts, args),
+        GideonInstruction::UpdateIssuer(args) => update_issuer(program_id, accounts, args),
+        GideonInstruction::VerifyIssuer(args) => verify_issuer(program_id, accounts, args),
+        GideonInstruction::MigrateMintAuthority => migrate_mint_authority(program_id, accounts),
+        GideonInstruction::OpenDispute => open_dispute(program_id, accounts),
+        GideonInstruction::ResolveDispute(args) => resolve_dispute(program_id, accounts, args),
+        GideonInstruction::LapseDispute => lapse_dispute(program_id, accounts),
+        GideonInstruction::ApproveMilestone(args) => approve_milestone(program_id, accounts, args),
+        GideonInstruction::ReclaimMilestone(args) => reclaim_milestone(program_id, accounts, args),
+        GideonInstruction::ClaimFulfilment => claim_fulfilment(program_id, accounts),
+        GideonInstruction::ReleaseFulfilment => release_fulfilment(program_id, accounts),
+        GideonInstruction::CreateCampaign(args) => create_campaign(program_id, accounts, args),
+        GideonInstruction::ClaimVoucher(args) => claim_voucher(program_id, accounts, args),
+        GideonInstruction::CloseCampaign => close_campaign(program_id, accounts),
+        GideonInstruction::InitGrantVault(args) => init_grant_vault(program_id, accounts, args),
+        GideonInstruction::WithdrawGrantVault(args) => {
+            withdraw_grant_vault(program_id, accounts, args)
+        }
+        GideonInstruction::RedeemGrant(args) => redeem_grant(program_id, accounts, args),
+        GideonInstruction::SetRefundAddress(args) => set_refund_address(program_id, accounts, args),
+        GideonInstruction::FreezeVoucher(args) => freeze_voucher(program_id, accounts, args),
+        GideonInstruction::ThawVoucher => thaw_voucher(program_id, accounts),
+        GideonInstruction::RecoverVoucher => recover_voucher(program_id, accounts),
+        GideonInstruction::RegisterMerchant(args) => register_merchant(program_id, accounts, args),
+        GideonInstruction::RemoveMerchant => remove_merchant(program_id, accounts),
+        GideonInstruction::RejectFulfilment => reject_fulfilment(program_id, accounts),
+        GideonInstruction::MigrateEscrow => migrate_escrow(program_id, accounts),
     }
 }
@@ -1,4 +1,10 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, borsh1::try_from_slice_unchecked, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::GideonError;
 
 #[derive(BorshDeserialize, BorshSerialize, Debug)]
 pub struct MintAuthorityPda {
@@ -9,3 +15,63 @@ impl MintAuthorityPda {
     pub const SEED_PREFIX: &'static str = "mint_authority";
     pub const SIZE: usize = 8 + 8;
 }
+
+/// A checked mint authority PDA, able to produce the seeds to sign for it.
+///
+/// Vouchers are minted under a PDA seeded by their issuer. Vouchers issued before that
+/// used a single global PDA, which is still accepted where `allow_global` is set so they
+/// can be redeemed and migrated. Their escrows must first be converted to the current layout
+/// with `MigrateEscrow`.
+pub struct MintAuthoritySigner {
+    pub issuer: Option<Pubkey>,
+    bump: [u8; 1],
+}
+
+impl MintAuthoritySigner {
+    pub fn load(
+        program_id: &Pubkey,
+        mint_authority: &AccountInfo,
+        issuer: &Pubkey,
+        allow_global: bool,
+    ) -> Result<Self, ProgramError> {
+        if mint_authority.owner != program_id {
+            msg!("Invalid mint authority account");
+            return Err(GideonError::InvalidMintAuthority.into());
+        }
+        let bump =
+            try_from_slice_unchecked::<MintAuthorityPda>(&mint_authority.data.borrow())?.bump;
+
+        let signer = Self {
+            issuer: Some(*issuer),
+            bump: [bump],
+        };
+        if Pubkey::create_program_address(&signer.seeds(), program_id) == Ok(*mint_authority.key) {
+            return Ok(signer);
+        }
+
+        let global = Self {
+            issuer: None,
+            bump: [bump],
+        };
+        if allow_global
+            && Pubkey::create_program_address(&global.seeds(), program_id)
+                == Ok(*mint_authority.key)
+        {
+            return Ok(global);
+        }
+
+        msg!("Invalid mint authority account");
+        Err(GideonError::InvalidMintAuthority.into())
+    }
+
+    pub fn seeds(&self) -> Vec<&[u8]> {
+        match &self.issuer {
+            Some(issuer) => vec![
+                MintAuthorityPda::SEED_PREFIX.as_bytes(),
+                issuer.as_ref(),
+                &self.bump,
+            ],
+            None => vec![MintAuthorityPda::SEED_PREFIX.as_bytes(), &self.bump],
+        }
+    }
+}
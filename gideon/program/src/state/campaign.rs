@@ -0,0 +1,83 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, borsh1::try_from_slice_unchecked, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::GideonError;
+
+/// An airdrop of vouchers, committed to as a Merkle root and minted only when claimed.
+///
+/// The campaign account doubles as the vault, holding the value of every unclaimed voucher
+/// plus the rent for the escrows created as they are claimed.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct Campaign {
+    pub issuer: Pubkey,
+    /// Brand signer that funded the campaign, who gets unclaimed funds back
+    pub authority: Pubkey,
+    pub campaign_id: u64,
+    /// Root of the tree of `(index, recipient, amount, expiry)` leaves, see `merkle`
+    pub merkle_root: [u8; 32],
+    pub leaf_count: u32,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    /// Unix timestamp (seconds) after which vouchers can no longer be claimed
+    pub claim_until: i64,
+    /// Settlement grace period given to every claimed voucher
+    pub grace_period: i64,
+    /// Rent set aside in the vault for each escrow
+    pub escrow_rent: u64,
+    pub title: String,
+    pub description: String,
+    pub symbol: String,
+    pub uri: String,
+    /// One bit per leaf, set once that leaf has been claimed
+    pub claimed: Vec<u8>,
+    pub bump: u8,
+}
+
+impl Campaign {
+    pub const SEED_PREFIX: &'static str = "campaign";
+
+    /// Keeps the claimed bitmap within the 10KiB an account can be created with
+    pub const MAX_LEAVES: u32 = 65_536;
+
+    pub const MAX_TITLE_LEN: usize = 32;
+    pub const MAX_DESCRIPTION_LEN: usize = 200;
+    pub const MAX_SYMBOL_LEN: usize = 10;
+    pub const MAX_URI_LEN: usize = 200;
+
+    /// Loads a campaign, checking it is the campaign PDA of its issuer and id.
+    pub fn load(program_id: &Pubkey, campaign_account: &AccountInfo) -> Result<Self, ProgramError> {
+        if campaign_account.owner != program_id {
+            msg!("Invalid campaign account");
+            return Err(GideonError::InvalidCampaign.into());
+        }
+        let campaign = try_from_slice_unchecked::<Self>(&campaign_account.data.borrow())
+            .map_err(|_| GideonError::InvalidCampaign)?;
+
+        let campaign_pda = Pubkey::create_program_address(
+            &[
+                Self::SEED_PREFIX.as_bytes(),
+                campaign.issuer.as_ref(),
+                &campaign.campaign_id.to_le_bytes(),
+                &[campaign.bump],
+            ],
+            program_id,
+        )?;
+        if campaign_account.key != &campaign_pda {
+            msg!("Invalid campaign account");
+            return Err(GideonError::InvalidCampaign.into());
+        }
+
+        Ok(campaign)
+    }
+
+    pub fn is_claimed(&self, index: u32) -> bool {
+        self.claimed[index as usize / 8] & (1 << (index % 8)) != 0
+    }
+
+    pub fn set_claimed(&mut self, index: u32) {
+        self.claimed[index as usize / 8] |= 1 << (index % 8);
+    }
+}
@@ -0,0 +1,103 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, borsh1::try_from_slice_unchecked, entrypoint::ProgramResult, msg,
+    program_error::ProgramError, pubkey::Pubkey,
+};
+
+use crate::error::GideonError;
+
+/// Platform-wide settings, stored in a single PDA and controlled by the admin.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct Config {
+    pub admin: Pubkey,
+    /// Proposed new admin, who must accept before the handover completes
+    pub pending_admin: Option<Pubkey>,
+    pub treasury: Pubkey,
+    pub issuance_fee_bps: u16,
+    pub redemption_fee_bps: u16,
+    pub issuance_flat_fee: u64,
+    pub redemption_flat_fee: u64,
+    /// Bitmask of paused instruction groups, see the `PAUSE_*` flags
+    pub paused: u8,
+    /// Largest amount a single voucher may escrow
+    pub max_voucher_amount: u64,
+    /// Longest a voucher may stay valid, measured from issuance
+    pub max_validity_period: i64,
+    /// Longest settlement grace period a voucher may carry
+    pub max_grace_period: i64,
+    pub bump: u8,
+    /// Key allowed to pause, but not unpause, alongside the admin. Default key when unset
+    pub guardian: Pubkey,
+    /// Whether recovering a lost voucher also needs the admin's signature, on by default
+    pub recovery_requires_admin: bool,
+    /// Program that must own the price feeds fiat vouchers settle against. Fiat vouchers
+    /// can't be issued or redeemed while unset
+    pub oracle_program: Pubkey,
+    /// Space kept free so settings can be added without resizing the account
+    pub reserved: [u8; 31],
+}
+
+impl Config {
+    pub const ACCOUNT_SPACE: usize =
+        32 + 33 + 32 + 2 + 2 + 8 + 8 + 1 + 8 + 8 + 8 + 1 + 32 + 1 + 32 + 31;
+
+    pub const SEED_PREFIX: &'static str = "config";
+
+    pub const PAUSE_ISSUANCE: u8 = 1 << 0;
+    pub const PAUSE_REDEMPTION: u8 = 1 << 1;
+    pub const PAUSE_RECLAIM: u8 = 1 << 2;
+    pub const PAUSE_ALL: u8 = Self::PAUSE_ISSUANCE | Self::PAUSE_REDEMPTION | Self::PAUSE_RECLAIM;
+
+    /// Highest percentage fee the admin can set (10%)
+    pub const MAX_FEE_BPS: u16 = 1_000;
+
+    /// Highest flat fee the admin can set (0.1 SOL)
+    pub const MAX_FLAT_FEE: u64 = 100_000_000;
+
+    /// Fee charged to the issuer on top of the escrowed amount.
+    pub fn issuance_fee(&self, amount: u64) -> u64 {
+        fee(amount, self.issuance_fee_bps, self.issuance_flat_fee)
+    }
+
+    /// Issuance fee for `count` vouchers worth `total_amount` together, e.g. an airdrop.
+    pub fn batch_issuance_fee(&self, total_amount: u64, count: u64) -> u64 {
+        fee(
+            total_amount,
+            self.issuance_fee_bps,
+            self.issuance_flat_fee.saturating_mul(count),
+        )
+    }
+
+    /// Fee taken out of a redemption payout, never more than the payout itself.
+    pub fn redemption_fee(&self, amount: u64) -> u64 {
+        fee(amount, self.redemption_fee_bps, self.redemption_flat_fee).min(amount)
+    }
+
+    /// Loads the config, checking it is the program's config PDA.
+    pub fn load(program_id: &Pubkey, config_account: &AccountInfo) -> Result<Self, ProgramError> {
+        let (config_pda, _) =
+            Pubkey::find_program_address(&[Self::SEED_PREFIX.as_bytes()], program_id);
+        if config_account.key != &config_pda || config_account.owner != program_id {
+            msg!("Invalid config account");
+            return Err(GideonError::InvalidConfigAccount.into());
+        }
+
+        try_from_slice_unchecked::<Self>(&config_account.data.borrow())
+            .map_err(|_| GideonError::InvalidConfigAccount.into())
+    }
+
+    /// Checks that `treasury` is the configured treasury account.
+    pub fn check_treasury(&self, treasury: &AccountInfo) -> ProgramResult {
+        if self.treasury != *treasury.key {
+            msg!("Invalid treasury account");
+            return Err(GideonError::InvalidTreasuryAccount.into());
+        }
+
+        Ok(())
+    }
+}
+
+fn fee(amount: u64, bps: u16, flat_fee: u64) -> u64 {
+    let percentage_fee = (amount as u128 * bps as u128 / 10_000) as u64;
+    percentage_fee.saturating_add(flat_fee)
+}
@@ -1,27 +1,462 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::Pubkey;
+use solana_program::{
+    account_info::AccountInfo, borsh1::try_from_slice_unchecked, entrypoint::ProgramResult, msg,
+    program_error::ProgramError, pubkey::Pubkey,
+};
+
+use crate::{error::GideonError, state::status::VoucherStatus};
 
 #[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
 pub struct Escrow {
     pub payer: Pubkey,
     pub recipient: Pubkey,
+    /// Value still held in escrow for the recipient
     pub amount: u64,
     pub bump: u8,
     pub voucher_mint: Pubkey,
+    /// Unix timestamp (seconds) from which the voucher can be redeemed
+    pub valid_from: i64,
+    /// Unix timestamp (seconds) after which the voucher expires
+    pub valid_until: i64,
+    /// Seconds after `valid_until` during which merchants can still settle redemptions
+    pub grace_period: i64,
+    /// Number of times the voucher can be redeemed in total
+    pub max_redemptions: u16,
+    /// Number of redemptions left before the voucher is burned
+    pub redemptions_remaining: u16,
+    /// Value paid out to the recipient on each redemption
+    pub per_redemption_amount: u64,
+    /// SHA-256 of the voucher mint and a secret code that lets anyone presenting it redeem
+    pub code_hash: Option<[u8; 32]>,
+    /// Address chosen by the recipient to receive redemption payouts instead of themselves
+    pub payout_address: Option<Pubkey>,
+    /// Issuer account of the brand that issued the voucher
+    pub issuer: Pubkey,
+    /// Third party who can settle a dispute over the voucher, if any
+    pub arbiter: Option<Pubkey>,
+    /// Share of the escrowed value paid to the arbiter for settling a dispute
+    pub arbiter_fee_bps: u16,
+    /// Unix timestamp (seconds) at which an open dispute was raised, freezing the escrow
+    pub disputed_at: Option<i64>,
+    /// Number of disputes raised over the voucher, capped at `MAX_DISPUTES`
+    pub disputes_opened: u8,
+    /// Number of milestones in the escrow's schedule, zero unless paid out in tranches
+    pub milestone_count: u8,
+    /// Seconds the holder has to confirm a fulfilment before it is released anyway, zero
+    /// unless redemptions need delivery confirmation
+    pub confirmation_window: i64,
+    /// Fulfilment claimed by a merchant and awaiting the holder's confirmation
+    pub fulfilment: Option<Fulfilment>,
+    /// Address refunded instead of the payer when the voucher expires unused
+    pub refund_address: Option<Pubkey>,
+    /// Lamports held on top of the amount, paid to whoever reclaims the escrow once expired
+    pub crank_bounty: u64,
+    /// Where the voucher is in its lifecycle, only changed through `transition`
+    pub status: VoucherStatus,
+    /// Whether the program kept freeze authority so the issuer can freeze the voucher
+    pub freezable: bool,
+    /// Face value in a fiat currency, paid out at the oracle price instead of the amount
+    pub fiat: Option<FiatTerms>,
+    /// Partner order or tracking reference the voucher was issued against
+    pub external_ref: Option<Vec<u8>>,
+    /// Recipient and voucher mint the escrow address was derived from, kept once recovery
+    /// has moved the voucher to a new holder and mint
+    pub original_seeds: Option<EscrowSeeds>,
 }
 
 impl Escrow {
-    pub const ACCOUNT_SPACE: usize = 32 + 32 + 16 + 1 + 32;
+    pub const ACCOUNT_SPACE: usize = 32 // payer
+        + 32 // recipient
+        + 16 // amount
+        + 1 // bump
+        + 32 // voucher_mint
+        + 8 // valid_from
+        + 8 // valid_until
+        + 8 // grace_period
+        + 2 // max_redemptions
+        + 2 // redemptions_remaining
+        + 8 // per_redemption_amount
+        + 1 + 32 // code_hash
+        + 1 + 32 // payout_address
+        + 32 // issuer
+        + 33 // arbiter
+        + 2 // arbiter_fee_bps
+        + 9 // disputed_at
+        + 1 // disputes_opened
+        + 1 // milestone_count
+        + 8 // confirmation_window
+        + 1 + Fulfilment::SIZE // fulfilment
+        + 1 + 32 // refund_address
+        + 8 // crank_bounty
+        + 1 // status
+        + 1 // freezable
+        + 1 + FiatTerms::SIZE // fiat
+        + 1 + 4 + Self::MAX_EXTERNAL_REF_LEN // external_ref
+        + 1 + EscrowSeeds::SIZE; // original_seeds
 
     pub const SEED_PREFIX: &'static str = "escrow";
 
-    pub fn new(payer: Pubkey, recipient: Pubkey, amount: u64, bump: u8, voucher_mint: Pubkey) -> Self {
+    /// Hard cap on the configurable validity period, measured from issuance (5 years)
+    pub const MAX_VALIDITY_PERIOD: i64 = 60 * 60 * 24 * 365 * 5;
+
+    /// Hard cap on the configurable settlement grace period (30 days)
+    pub const MAX_GRACE_PERIOD: i64 = 60 * 60 * 24 * 30;
+
+    /// Highest arbiter fee an issuer can set (10%)
+    pub const MAX_ARBITER_FEE_BPS: u16 = 1_000;
+
+    /// Time the arbiter has to settle a dispute before it lapses (14 days)
+    pub const DISPUTE_TIMEOUT: i64 = 60 * 60 * 24 * 14;
+
+    /// Most disputes that can be raised over a voucher, so lapsed disputes can't keep
+    /// extending its validity window
+    pub const MAX_DISPUTES: u8 = 2;
+
+    /// Longest delivery confirmation window an issuer can set (30 days)
+    pub const MAX_CONFIRMATION_WINDOW: i64 = 60 * 60 * 24 * 30;
+
+    /// Highest bounty an issuer can offer for reclaiming an expired escrow (0.01 SOL)
+    pub const MAX_CRANK_BOUNTY: u64 = 10_000_000;
+
+    /// Lowest collateral a fiat voucher can be issued with, relative to its face value (110%)
+    pub const MIN_FIAT_COLLATERAL_BPS: u64 = 11_000;
+
+    /// Longest external reference a voucher or redemption can carry
+    pub const MAX_EXTERNAL_REF_LEN: usize = 32;
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        payer: Pubkey,
+        recipient: Pubkey,
+        amount: u64,
+        bump: u8,
+        voucher_mint: Pubkey,
+        valid_from: i64,
+        valid_until: i64,
+        grace_period: i64,
+        max_redemptions: u16,
+        per_redemption_amount: u64,
+        code_hash: Option<[u8; 32]>,
+        issuer: Pubkey,
+        arbiter: Option<Pubkey>,
+        arbiter_fee_bps: u16,
+        confirmation_window: i64,
+    ) -> Self {
         Self {
             payer,
             recipient,
             amount,
             bump,
             voucher_mint,
+            valid_from,
+            valid_until,
+            grace_period,
+            max_redemptions,
+            redemptions_remaining: max_redemptions,
+            per_redemption_amount,
+            code_hash,
+            payout_address: None,
+            issuer,
+            arbiter,
+            arbiter_fee_bps,
+            disputed_at: None,
+            disputes_opened: 0,
+            milestone_count: 0,
+            confirmation_window,
+            fulfilment: None,
+            refund_address: None,
+            crank_bounty: 0,
+            status: VoucherStatus::Issued,
+            freezable: false,
+            fiat: None,
+            external_ref: None,
+            original_seeds: None,
+        }
+    }
+
+    /// Loads an escrow, checking it is owned by the program.
+    pub fn load(program_id: &Pubkey, escrow_account: &AccountInfo) -> Result<Self, ProgramError> {
+        if escrow_account.owner != program_id {
+            msg!("Invalid voucher escrow account");
+            return Err(GideonError::InvalidVoucherEscrowAccount.into());
+        }
+
+        try_from_slice_unchecked::<Self>(&escrow_account.data.borrow())
+            .map_err(|_| GideonError::InvalidVoucherEscrowAccount.into())
+    }
+
+    /// Recipient and voucher mint the escrow address is derived from, which recovery
+    /// doesn't change.
+    pub fn seeds(&self) -> EscrowSeeds {
+        self.original_seeds.unwrap_or(EscrowSeeds {
+            recipient: self.recipient,
+            voucher_mint: self.voucher_mint,
+        })
+    }
+
+    /// Last moment at which the voucher can still be redeemed, including the grace period.
+    pub fn settlement_deadline(&self) -> i64 {
+        self.valid_until.saturating_add(self.grace_period)
+    }
+
+    /// Status of the voucher at `now`, counting an issued voucher as active once its
+    /// validity window has opened.
+    pub fn current_status(&self, now: i64) -> VoucherStatus {
+        match self.status {
+            VoucherStatus::Issued if now >= self.valid_from => VoucherStatus::Active,
+            status => status,
+        }
+    }
+
+    /// Status a disputed or frozen voucher returns to, based on what has been paid out.
+    pub fn live_status(&self, now: i64) -> VoucherStatus {
+        let issued_amount = self
+            .per_redemption_amount
+            .saturating_mul(self.max_redemptions as u64);
+        if self.amount < issued_amount {
+            VoucherStatus::PartiallyRedeemed
+        } else if now < self.valid_from {
+            VoucherStatus::Issued
+        } else {
+            VoucherStatus::Active
+        }
+    }
+
+    /// Number of the next redemption, counting from 1, which its receipt is seeded with.
+    pub fn next_redemption(&self) -> u16 {
+        self.max_redemptions
+            .saturating_sub(self.redemptions_remaining)
+            .saturating_add(1)
+    }
+
+    /// Status after a redemption has been recorded, redeemed once no uses are left.
+    pub fn redeemed_status(&self) -> VoucherStatus {
+        if self.redemptions_remaining == 0 || self.amount == 0 {
+            VoucherStatus::Redeemed
+        } else {
+            VoucherStatus::PartiallyRedeemed
+        }
+    }
+
+    /// Moves the voucher to `next`, failing if its lifecycle does not allow it.
+    pub fn transition(&mut self, next: VoucherStatus, now: i64) -> ProgramResult {
+        let current = self.current_status(now);
+        if !current.can_transition_to(next) {
+            msg!(
+                "Invalid voucher status transition: {:?} to {:?}",
+                current,
+                next
+            );
+            return Err(GideonError::InvalidStatusTransition.into());
+        }
+        self.status = next;
+
+        Ok(())
+    }
+
+    /// Fails if `external_ref` is longer than a receipt can hold.
+    pub fn check_external_ref(external_ref: Option<&[u8]>) -> ProgramResult {
+        if external_ref.is_some_and(|external_ref| external_ref.len() > Self::MAX_EXTERNAL_REF_LEN)
+        {
+            msg!("External reference too long");
+            return Err(GideonError::InvalidExternalRef.into());
+        }
+
+        Ok(())
+    }
+
+    /// Fails while a dispute is open, as the escrow is frozen until it is settled.
+    pub fn check_not_disputed(&self) -> ProgramResult {
+        if self.disputed_at.is_some() {
+            msg!("Escrow disputed");
+            return Err(GideonError::EscrowDisputed.into());
+        }
+
+        Ok(())
+    }
+
+    /// Fails while the issuer has the voucher frozen.
+    pub fn check_not_frozen(&self) -> ProgramResult {
+        if self.status == VoucherStatus::Frozen {
+            msg!("Voucher frozen");
+            return Err(GideonError::VoucherFrozen.into());
         }
+
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Escrow layout written before vouchers had a validity window, redemption limits or an
+/// issuer. Every later layout starts with these fields, and escrows still in this layout
+/// are converted with `MigrateEscrow`.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct LegacyEscrow {
+    pub payer: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+    pub voucher_mint: Pubkey,
+}
+
+impl LegacyEscrow {
+    /// Size legacy escrows were allocated with, which reserved 16 bytes for the amount
+    pub const ACCOUNT_SPACE: usize = 32 + 32 + 16 + 1 + 32;
+
+    /// Checks `escrow_account` is the escrow PDA these fields were seeded with.
+    pub fn check_address(
+        &self,
+        program_id: &Pubkey,
+        escrow_account: &AccountInfo,
+    ) -> ProgramResult {
+        let escrow_pda = Pubkey::create_program_address(
+            &[
+                Escrow::SEED_PREFIX.as_bytes(),
+                self.payer.as_ref(),
+                self.recipient.as_ref(),
+                self.voucher_mint.as_ref(),
+                &[self.bump],
+            ],
+            program_id,
+        );
+        if escrow_account.owner != program_id || escrow_pda != Ok(*escrow_account.key) {
+            msg!("Invalid voucher escrow account");
+            return Err(GideonError::InvalidVoucherEscrowAccount.into());
+        }
+
+        Ok(())
+    }
+
+    /// Converts to the current layout as a single-use voucher for the whole amount, valid
+    /// until `valid_until`. `issuer` is the default key while the payer has no issuer.
+    pub fn upgrade(self, valid_until: i64, issuer: Pubkey) -> Escrow {
+        Escrow::new(
+            self.payer,
+            self.recipient,
+            self.amount,
+            self.bump,
+            self.voucher_mint,
+            0,
+            valid_until,
+            0,
+            1,
+            self.amount,
+            None,
+            issuer,
+            None,
+            0,
+            0,
+        )
+    }
+}
+
+/// Keys an escrow PDA is seeded with, besides its payer.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq)]
+pub struct EscrowSeeds {
+    pub recipient: Pubkey,
+    pub voucher_mint: Pubkey,
+}
+
+impl EscrowSeeds {
+    pub const SIZE: usize = 32 + 32;
+}
+
+/// A redemption claimed by a merchant, released once the holder confirms delivery.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct Fulfilment {
+    pub merchant: Pubkey,
+    /// Voucher holder at the time of the claim, who can confirm receipt
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub claimed_at: i64,
+}
+
+impl Fulfilment {
+    pub const SIZE: usize = 32 + 32 + 8 + 8;
+}
+
+/// Face value of a voucher denominated in a fiat currency, with the escrowed SOL as collateral.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct FiatTerms {
+    /// ISO 4217 currency code, e.g. `ZAR`
+    pub currency: [u8; 3],
+    /// Face value in minor units of the currency, e.g. cents
+    pub face_value: u64,
+    /// Decimals of the currency's minor unit, e.g. 2 for cents
+    pub decimals: u8,
+    /// Price account quoting one SOL in the currency
+    pub price_feed: Pubkey,
+}
+
+impl FiatTerms {
+    pub const SIZE: usize = 3 + 8 + 1 + 32;
+
+    /// Most minor unit decimals a currency can have
+    pub const MAX_DECIMALS: u8 = 6;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_escrow_upgrades_and_redeems() {
+        let legacy = LegacyEscrow {
+            payer: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            amount: 1_000_000,
+            bump: 254,
+            voucher_mint: Pubkey::new_unique(),
+        };
+        let mut data = borsh::to_vec(&legacy).unwrap();
+        assert!(data.len() <= LegacyEscrow::ACCOUNT_SPACE);
+        data.resize(LegacyEscrow::ACCOUNT_SPACE, 0);
+
+        // Migrating reads the legacy layout and writes the current one into the grown account
+        let legacy = try_from_slice_unchecked::<LegacyEscrow>(&data).unwrap();
+        let upgraded = legacy.clone().upgrade(1_700_000_000, Pubkey::default());
+        data.resize(Escrow::ACCOUNT_SPACE, 0);
+        upgraded.serialize(&mut &mut data[..]).unwrap();
+
+        let mut escrow = try_from_slice_unchecked::<Escrow>(&data).unwrap();
+        assert_eq!(escrow.payer, legacy.payer);
+        assert_eq!(escrow.recipient, legacy.recipient);
+        assert_eq!(escrow.voucher_mint, legacy.voucher_mint);
+        assert_eq!(escrow.bump, legacy.bump);
+        assert_eq!(escrow.settlement_deadline(), 1_700_000_000);
+
+        // A single redemption pays out the whole amount and completes the voucher
+        let now = 1_600_000_000;
+        assert_eq!(escrow.current_status(now), VoucherStatus::Active);
+        assert_eq!(escrow.next_redemption(), 1);
+        let amount = escrow.per_redemption_amount.min(escrow.amount);
+        assert_eq!(amount, legacy.amount);
+        escrow.amount -= amount;
+        escrow.redemptions_remaining = escrow.redemptions_remaining.saturating_sub(1);
+        escrow.transition(escrow.redeemed_status(), now).unwrap();
+        assert_eq!(escrow.status, VoucherStatus::Redeemed);
+    }
+
+    #[test]
+    fn recovery_keeps_original_seeds() {
+        let mut escrow = LegacyEscrow {
+            payer: Pubkey::new_unique(),
+            recipient: Pubkey::new_unique(),
+            amount: 1_000_000,
+            bump: 254,
+            voucher_mint: Pubkey::new_unique(),
+        }
+        .upgrade(1_700_000_000, Pubkey::new_unique());
+        let seeds = escrow.seeds();
+
+        // Recovering twice still records the keys the escrow was first derived from
+        for _ in 0..2 {
+            escrow.original_seeds = Some(escrow.seeds());
+            escrow.recipient = Pubkey::new_unique();
+            escrow.voucher_mint = Pubkey::new_unique();
+        }
+        assert_eq!(escrow.seeds(), seeds);
+
+        let data = borsh::to_vec(&escrow).unwrap();
+        assert!(data.len() <= Escrow::ACCOUNT_SPACE);
+    }
+}
@@ -0,0 +1,77 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, borsh1::try_from_slice_unchecked, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::GideonError;
+
+/// An issuer's pot of funds for vouchers handed out as signed off-chain grants.
+///
+/// Anyone can top the vault up with a plain transfer. Each redeemed grant draws the voucher
+/// value, the escrow rent and the issuance fee from it.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct GrantVault {
+    pub issuer: Pubkey,
+    pub title: String,
+    pub description: String,
+    pub symbol: String,
+    pub uri: String,
+    pub bump: u8,
+}
+
+impl GrantVault {
+    pub const SEED_PREFIX: &'static str = "grant_vault";
+
+    pub const MAX_TITLE_LEN: usize = 32;
+    pub const MAX_DESCRIPTION_LEN: usize = 200;
+    pub const MAX_SYMBOL_LEN: usize = 10;
+    pub const MAX_URI_LEN: usize = 200;
+
+    pub const ACCOUNT_SPACE: usize = 32
+        + (4 + Self::MAX_TITLE_LEN)
+        + (4 + Self::MAX_DESCRIPTION_LEN)
+        + (4 + Self::MAX_SYMBOL_LEN)
+        + (4 + Self::MAX_URI_LEN)
+        + 1;
+
+    /// Loads a grant vault, checking it is the vault PDA of its issuer.
+    pub fn load(program_id: &Pubkey, vault_account: &AccountInfo) -> Result<Self, ProgramError> {
+        if vault_account.owner != program_id {
+            msg!("Invalid grant vault account");
+            return Err(GideonError::InvalidGrantVault.into());
+        }
+        let vault = try_from_slice_unchecked::<Self>(&vault_account.data.borrow())
+            .map_err(|_| GideonError::InvalidGrantVault)?;
+
+        let vault_pda = Pubkey::create_program_address(
+            &[
+                Self::SEED_PREFIX.as_bytes(),
+                vault.issuer.as_ref(),
+                &[vault.bump],
+            ],
+            program_id,
+        )?;
+        if vault_account.key != &vault_pda {
+            msg!("Invalid grant vault account");
+            return Err(GideonError::InvalidGrantVault.into());
+        }
+
+        Ok(vault)
+    }
+}
+
+/// Marks a grant nonce as used, recording the voucher it was redeemed for.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct GrantNonce {
+    pub issuer: Pubkey,
+    pub nonce: u64,
+    pub voucher_mint: Pubkey,
+    pub bump: u8,
+}
+
+impl GrantNonce {
+    pub const ACCOUNT_SPACE: usize = 32 + 8 + 32 + 1;
+
+    pub const SEED_PREFIX: &'static str = "grant_nonce";
+}
@@ -0,0 +1,69 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, borsh1::try_from_slice_unchecked, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::GideonError;
+
+/// A brand's on-chain profile. Vouchers record the issuer account that minted them, so
+/// wallets can show the brand and its verified status from chain data alone.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct Issuer {
+    pub authority: Pubkey,
+    pub display_name: String,
+    /// Set by the platform admin once the brand has been checked
+    pub verified: bool,
+    pub logo_uri: String,
+    pub website: String,
+    /// Extra keys allowed to issue vouchers for this brand
+    pub signers: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl Issuer {
+    pub const MAX_DISPLAY_NAME_LEN: usize = 64;
+    pub const MAX_LOGO_URI_LEN: usize = 200;
+    pub const MAX_WEBSITE_LEN: usize = 100;
+    pub const MAX_SIGNERS: usize = 5;
+
+    pub const ACCOUNT_SPACE: usize = 32
+        + (4 + Self::MAX_DISPLAY_NAME_LEN)
+        + 1
+        + (4 + Self::MAX_LOGO_URI_LEN)
+        + (4 + Self::MAX_WEBSITE_LEN)
+        + (4 + 32 * Self::MAX_SIGNERS)
+        + 1;
+
+    pub const SEED_PREFIX: &'static str = "issuer";
+
+    /// Loads an issuer, checking it is the issuer PDA of its stored authority.
+    pub fn load(program_id: &Pubkey, issuer_account: &AccountInfo) -> Result<Self, ProgramError> {
+        if issuer_account.owner != program_id {
+            msg!("Invalid issuer account");
+            return Err(GideonError::InvalidIssuerAccount.into());
+        }
+        let issuer = try_from_slice_unchecked::<Self>(&issuer_account.data.borrow())
+            .map_err(|_| GideonError::InvalidIssuerAccount)?;
+
+        let issuer_pda = Pubkey::create_program_address(
+            &[
+                Self::SEED_PREFIX.as_bytes(),
+                issuer.authority.as_ref(),
+                &[issuer.bump],
+            ],
+            program_id,
+        )?;
+        if issuer_account.key != &issuer_pda {
+            msg!("Invalid issuer account");
+            return Err(GideonError::InvalidIssuerAccount.into());
+        }
+
+        Ok(issuer)
+    }
+
+    /// Whether `key` may act for this issuer.
+    pub fn is_signer(&self, key: &Pubkey) -> bool {
+        self.authority == *key || self.signers.contains(key)
+    }
+}
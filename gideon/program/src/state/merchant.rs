@@ -0,0 +1,53 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, borsh1::try_from_slice_unchecked, entrypoint::ProgramResult, msg,
+    pubkey::Pubkey,
+};
+
+use crate::error::GideonError;
+
+/// A merchant registered by an issuer to redeem the issuer's vouchers with their codes, to
+/// claim fulfilments and to attest which product it supplied for product-bound vouchers.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct Merchant {
+    pub issuer: Pubkey,
+    pub merchant: Pubkey,
+    pub bump: u8,
+}
+
+impl Merchant {
+    pub const ACCOUNT_SPACE: usize = 32 + 32 + 1;
+
+    pub const SEED_PREFIX: &'static str = "merchant";
+
+    /// Checks `merchant_account` registers `merchant` with `issuer`.
+    pub fn check_registered(
+        program_id: &Pubkey,
+        merchant_account: &AccountInfo,
+        issuer: &Pubkey,
+        merchant: &Pubkey,
+    ) -> ProgramResult {
+        let registered = merchant_account.owner == program_id
+            && try_from_slice_unchecked::<Self>(&merchant_account.data.borrow())
+                .ok()
+                .and_then(|registration| {
+                    Pubkey::create_program_address(
+                        &[
+                            Self::SEED_PREFIX.as_bytes(),
+                            issuer.as_ref(),
+                            merchant.as_ref(),
+                            &[registration.bump],
+                        ],
+                        program_id,
+                    )
+                    .ok()
+                })
+                .is_some_and(|merchant_pda| merchant_account.key == &merchant_pda);
+        if !registered {
+            msg!("Merchant not registered with issuer");
+            return Err(GideonError::MerchantNotRegistered.into());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, borsh1::try_from_slice_unchecked, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::GideonError;
+
+/// One tranche of a milestone escrow.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct Milestone {
+    /// SHA-256 of the off-chain deliverable description
+    pub description_hash: [u8; 32],
+    pub amount: u64,
+    /// Unix timestamp (seconds) after which the payer can reclaim the tranche if not approved
+    pub deadline: i64,
+    pub status: MilestoneStatus,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MilestoneStatus {
+    Pending,
+    Approved,
+    Reclaimed,
+}
+
+/// Ordered tranches released from an escrow as the payer approves each deliverable.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct MilestoneSchedule {
+    pub escrow: Pubkey,
+    pub milestones: Vec<Milestone>,
+    pub bump: u8,
+}
+
+impl MilestoneSchedule {
+    pub const SEED_PREFIX: &'static str = "milestones";
+
+    pub const MAX_MILESTONES: usize = 10;
+
+    const MILESTONE_SPACE: usize = 32 + 8 + 8 + 1;
+
+    pub fn account_space(milestone_count: usize) -> usize {
+        32 + 4 + milestone_count * Self::MILESTONE_SPACE + 1
+    }
+
+    /// Loads the schedule, checking it is the milestone PDA of `escrow`.
+    pub fn load(
+        program_id: &Pubkey,
+        schedule_account: &AccountInfo,
+        escrow: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        if schedule_account.owner != program_id {
+            msg!("Invalid milestone schedule account");
+            return Err(GideonError::InvalidMilestoneSchedule.into());
+        }
+        let schedule = try_from_slice_unchecked::<Self>(&schedule_account.data.borrow())
+            .map_err(|_| GideonError::InvalidMilestoneSchedule)?;
+
+        let schedule_pda = Pubkey::create_program_address(
+            &[
+                Self::SEED_PREFIX.as_bytes(),
+                escrow.as_ref(),
+                &[schedule.bump],
+            ],
+            program_id,
+        )?;
+        if schedule_account.key != &schedule_pda || schedule.escrow != *escrow {
+            msg!("Invalid milestone schedule account");
+            return Err(GideonError::InvalidMilestoneSchedule.into());
+        }
+
+        Ok(schedule)
+    }
+
+    /// Whether every tranche has been approved or reclaimed.
+    pub fn is_settled(&self) -> bool {
+        self.milestones
+            .iter()
+            .all(|milestone| milestone.status != MilestoneStatus::Pending)
+    }
+}
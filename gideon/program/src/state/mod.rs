@@ -1,2 +1,11 @@
 pub mod authority;
+pub mod campaign;
+pub mod config;
 pub mod escrow;
+pub mod grant;
+pub mod issuer;
+pub mod merchant;
+pub mod milestone;
+pub mod nonce;
+pub mod receipt;
+pub mod status;
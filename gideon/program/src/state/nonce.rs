@@ -0,0 +1,17 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// Tracks the last off-chain redemption authorization used for a voucher,
+/// so a signed authorization cannot be replayed.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct RedemptionNonce {
+    pub voucher_mint: Pubkey,
+    pub last_nonce: u64,
+    pub bump: u8,
+}
+
+impl RedemptionNonce {
+    pub const ACCOUNT_SPACE: usize = 32 + 8 + 1;
+
+    pub const SEED_PREFIX: &'static str = "redemption_nonce";
+}
@@ -0,0 +1,95 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// Permanent record of one redemption, kept after the escrow is closed.
+///
+/// Every field has a fixed size, so receipts can be looked up with `getProgramAccounts`
+/// memcmp filters at these offsets:
+///
+/// | Offset | Size | Field          |
+/// |--------|------|----------------|
+/// | 0      | 32   | `voucher_mint` |
+/// | 32     | 32   | `issuer`       |
+/// | 64     | 32   | `holder`       |
+/// | 96     | 32   | `merchant`     |
+/// | 128    | 8    | `amount`       |
+/// | 136    | 8    | `timestamp`    |
+/// | 144    | 8    | `slot`         |
+/// | 152    | 2    | `redemption`   |
+/// | 154    | 32   | `product`      |
+/// | 186    | 32   | `external_ref` |
+/// | 218    | 1    | `bump`         |
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct RedemptionReceipt {
+    pub voucher_mint: Pubkey,
+    /// Issuer account of the brand that issued the voucher
+    pub issuer: Pubkey,
+    /// Holder of the voucher when it was redeemed
+    pub holder: Pubkey,
+    /// Account paid the redeemed value, the holder's payout address if they redeemed it
+    /// themselves
+    pub merchant: Pubkey,
+    /// Value paid out, before the protocol fee
+    pub amount: u64,
+    pub timestamp: i64,
+    pub slot: u64,
+    /// Which of the voucher's redemptions this was, counting from 1
+    pub redemption: u16,
+    /// Product attested by the merchant, all zeros if none
+    pub product: [u8; 32],
+    /// Partner order or tracking reference, zero-padded, all zeros if none
+    pub external_ref: [u8; 32],
+    pub bump: u8,
+}
+
+impl RedemptionReceipt {
+    pub const ACCOUNT_SPACE: usize = 32 + 32 + 32 + 32 + 8 + 8 + 8 + 2 + 32 + 32 + 1;
+
+    pub const SEED_PREFIX: &'static str = "receipt";
+
+    pub const VOUCHER_MINT_OFFSET: usize = 0;
+    pub const ISSUER_OFFSET: usize = 32;
+    pub const HOLDER_OFFSET: usize = 64;
+    pub const MERCHANT_OFFSET: usize = 96;
+    pub const EXTERNAL_REF_OFFSET: usize = 186;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_matches_documented_offsets() {
+        let receipt = RedemptionReceipt {
+            voucher_mint: Pubkey::new_from_array([1; 32]),
+            issuer: Pubkey::new_from_array([2; 32]),
+            holder: Pubkey::new_from_array([3; 32]),
+            merchant: Pubkey::new_from_array([4; 32]),
+            amount: 5,
+            timestamp: 6,
+            slot: 7,
+            redemption: 8,
+            product: [9; 32],
+            external_ref: [10; 32],
+            bump: 11,
+        };
+        let data = borsh::to_vec(&receipt).unwrap();
+
+        assert_eq!(data.len(), RedemptionReceipt::ACCOUNT_SPACE);
+        for (offset, field) in [
+            (RedemptionReceipt::VOUCHER_MINT_OFFSET, [1; 32]),
+            (RedemptionReceipt::ISSUER_OFFSET, [2; 32]),
+            (RedemptionReceipt::HOLDER_OFFSET, [3; 32]),
+            (RedemptionReceipt::MERCHANT_OFFSET, [4; 32]),
+            (RedemptionReceipt::EXTERNAL_REF_OFFSET, [10; 32]),
+        ] {
+            assert_eq!(data[offset..offset + 32], field);
+        }
+        assert_eq!(data[128..136], 5u64.to_le_bytes());
+        assert_eq!(data[136..144], 6i64.to_le_bytes());
+        assert_eq!(data[144..152], 7u64.to_le_bytes());
+        assert_eq!(data[152..154], 8u16.to_le_bytes());
+        assert_eq!(data[154..186], [9; 32]);
+        assert_eq!(data[218], 11);
+    }
+}
@@ -0,0 +1,141 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Where a voucher is in its lifecycle.
+///
+/// Vouchers start out `Issued` and are treated as `Active` from `valid_from` on, without a
+/// transaction having to record it. `Redeemed`, `Expired` and `Cancelled` are final: the
+/// escrow is closed when it reaches them.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoucherStatus {
+    /// Minted, but its validity window has not opened yet
+    Issued,
+    /// Redeemable, with nothing paid out yet
+    Active,
+    /// Redeemable, with part of the value already paid out
+    PartiallyRedeemed,
+    /// Fully paid out
+    Redeemed,
+    /// Reclaimed by the payer after expiry
+    Expired,
+    /// Settled by the arbiter of a dispute
+    Cancelled,
+    /// Frozen by an open dispute until it is resolved or lapses
+    Disputed,
+    /// Frozen by the issuer until thawed, or reclaimed once expired
+    Frozen,
+}
+
+impl VoucherStatus {
+    pub const ALL: [Self; 8] = [
+        Self::Issued,
+        Self::Active,
+        Self::PartiallyRedeemed,
+        Self::Redeemed,
+        Self::Expired,
+        Self::Cancelled,
+        Self::Disputed,
+        Self::Frozen,
+    ];
+
+    /// Whether the lifecycle allows moving from this status to `next`.
+    pub fn can_transition_to(self, next: Self) -> bool {
+        use VoucherStatus::*;
+
+        match self {
+            Issued => matches!(next, Active | Disputed | Frozen),
+            Active | PartiallyRedeemed => matches!(
+                next,
+                PartiallyRedeemed | Redeemed | Expired | Disputed | Frozen
+            ),
+            Disputed => matches!(next, Issued | Active | PartiallyRedeemed | Cancelled),
+            Frozen => matches!(next, Issued | Active | PartiallyRedeemed | Expired),
+            Redeemed | Expired | Cancelled => false,
+        }
+    }
+
+    /// Whether the voucher has reached the end of its lifecycle.
+    pub fn is_final(self) -> bool {
+        matches!(self, Self::Redeemed | Self::Expired | Self::Cancelled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VoucherStatus::{self, *};
+
+    /// Every transition the lifecycle allows, written out independently of the match in
+    /// `can_transition_to`.
+    const ALLOWED: &[(VoucherStatus, VoucherStatus)] = &[
+        (Issued, Active),
+        (Issued, Disputed),
+        (Issued, Frozen),
+        (Active, PartiallyRedeemed),
+        (Active, Redeemed),
+        (Active, Expired),
+        (Active, Disputed),
+        (Active, Frozen),
+        (PartiallyRedeemed, PartiallyRedeemed),
+        (PartiallyRedeemed, Redeemed),
+        (PartiallyRedeemed, Expired),
+        (PartiallyRedeemed, Disputed),
+        (PartiallyRedeemed, Frozen),
+        (Disputed, Issued),
+        (Disputed, Active),
+        (Disputed, PartiallyRedeemed),
+        (Disputed, Cancelled),
+        (Frozen, Issued),
+        (Frozen, Active),
+        (Frozen, PartiallyRedeemed),
+        (Frozen, Expired),
+    ];
+
+    #[test]
+    fn transition_table_is_exhaustive() {
+        for from in VoucherStatus::ALL {
+            for to in VoucherStatus::ALL {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    ALLOWED.contains(&(from, to)),
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn final_statuses_have_no_transitions() {
+        for from in VoucherStatus::ALL {
+            let has_exit = VoucherStatus::ALL
+                .iter()
+                .any(|to| from.can_transition_to(*to));
+            assert_eq!(from.is_final(), !has_exit, "{:?}", from);
+        }
+    }
+
+    #[test]
+    fn every_status_is_reachable_from_issued() {
+        let mut reached = vec![Issued];
+        let mut index = 0;
+        while let Some(&from) = reached.get(index) {
+            for to in VoucherStatus::ALL {
+                if from.can_transition_to(to) && !reached.contains(&to) {
+                    reached.push(to);
+                }
+            }
+            index += 1;
+        }
+
+        for status in VoucherStatus::ALL {
+            assert!(reached.contains(&status), "{:?}", status);
+        }
+    }
+
+    #[test]
+    fn statuses_serialize_to_one_byte() {
+        for (index, status) in VoucherStatus::ALL.iter().enumerate() {
+            assert_eq!(borsh::to_vec(status).unwrap(), vec![index as u8]);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use solana_program::{
+    account_info::AccountInfo,
+    ed25519_program,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+    sysvar::{
+        instructions::{load_current_index_checked, load_instruction_at_checked},
+        Sysvar,
+    },
+};
+
+use crate::error::GideonError;
+
+/// Size of the Ed25519 instruction header: signature count, padding and one set of offsets
+const ED25519_HEADER_LEN: usize = 16;
+
+/// Moves lamports out of an account owned by this program.
+pub fn transfer_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> ProgramResult {
+    let from_lamports = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    let to_lamports = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    **from.lamports.borrow_mut() = from_lamports;
+    **to.lamports.borrow_mut() = to_lamports;
+
+    Ok(())
+}
+
+/// Checks `token_program` is Token-2022 and owns each of `accounts`.
+///
+/// Mint authority PDAs are the permanent delegate of every voucher, so their signature must
+/// only ever reach Token-2022, and only for real voucher accounts.
+pub fn check_token_program(
+    token_program: &AccountInfo,
+    accounts: &[&AccountInfo],
+) -> ProgramResult {
+    if *token_program.key != spl_token_2022::id() {
+        msg!("Invalid token program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if accounts
+        .iter()
+        .any(|account| *account.owner != spl_token_2022::id())
+    {
+        msg!("Token account not owned by the token program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    Ok(())
+}
+
+/// Creates the PDA `account`, signed for with `seeds`, with `space` bytes owned by `owner` and
+/// `payer` funding its rent.
+///
+/// PDA addresses are known in advance, so anyone can send lamports to one first, which makes
+/// `create_account` fail. An already funded account is topped up to rent exemption, then
+/// allocated and assigned instead.
+pub fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let rent_exempt = Rent::get()?.minimum_balance(space);
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                account.key,
+                rent_exempt,
+                space as u64,
+                owner,
+            ),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+
+    let top_up = rent_exempt.saturating_sub(account.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, top_up),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, owner),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+/// Closes an account owned by this program, sending all of its lamports to `destination`.
+///
+/// The account is handed back to the system program with no data, so it cannot be revived
+/// as a program account later in the same transaction.
+pub fn close_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    transfer_lamports(account, destination, account.lamports())?;
+
+    account.assign(&system_program::ID);
+    account.realloc(0, false)?;
+
+    Ok(())
+}
+
+/// Checks that the instruction right before the current one is an Ed25519 program
+/// instruction verifying `signer`'s signature over exactly `message`.
+///
+/// The signature, public key and message must all be carried in the Ed25519 instruction
+/// itself, so none of them can be swapped out by pointing at another instruction.
+pub fn verify_ed25519_signature(
+    instructions_sysvar: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> ProgramResult {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    if current_index == 0 {
+        msg!("Missing Ed25519 signature instruction");
+        return Err(GideonError::InvalidSignature.into());
+    }
+
+    let instruction = load_instruction_at_checked(current_index as usize - 1, instructions_sysvar)?;
+    if instruction.program_id != ed25519_program::ID {
+        msg!("Missing Ed25519 signature instruction");
+        return Err(GideonError::InvalidSignature.into());
+    }
+
+    let data = &instruction.data;
+    if data.len() < ED25519_HEADER_LEN || data[0] != 1 {
+        msg!("Expected exactly one Ed25519 signature");
+        return Err(GideonError::InvalidSignature.into());
+    }
+
+    let read_u16 = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+    let signature_instruction_index = read_u16(4);
+    let public_key_offset = read_u16(6) as usize;
+    let public_key_instruction_index = read_u16(8);
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+    let message_instruction_index = read_u16(14);
+
+    if signature_instruction_index != u16::MAX
+        || public_key_instruction_index != u16::MAX
+        || message_instruction_index != u16::MAX
+    {
+        msg!("Ed25519 signature data must be inline");
+        return Err(GideonError::InvalidSignature.into());
+    }
+
+    let signed_key = data.get(public_key_offset..public_key_offset + 32);
+    let signed_message = data.get(message_offset..message_offset + message_size);
+    if signed_key != Some(signer.as_ref()) || signed_message != Some(message) {
+        msg!("Ed25519 signature does not match");
+        return Err(GideonError::InvalidSignature.into());
+    }
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 import { Buffer } from 'node:buffer';
+import { createHash, randomBytes } from 'node:crypto';
 import {
     ASSOCIATED_TOKEN_PROGRAM_ID,
     MetadataPointerInstruction,
@@ -13,22 +14,52 @@ import {
     transferChecked,
 } from '@solana/spl-token';
 import {
+    BPF_LOADER_UPGRADEABLE_PROGRAM_ID,
     Connection,
+    Ed25519Program,
     Keypair,
     LAMPORTS_PER_SOL,
     PublicKey,
     SYSVAR_CLOCK_PUBKEY,
+    SYSVAR_INSTRUCTIONS_PUBKEY,
     SYSVAR_RENT_PUBKEY,
+    Signer,
     SystemProgram,
     Transaction,
+    TransactionInstruction,
     sendAndConfirmTransaction,
 } from '@solana/web3.js';
-import { start } from 'solana-bankrun';
+import { Clock, start } from 'solana-bankrun';
 import {
+    InitEscrowArgs,
+    createApproveMilestoneInstruction,
+    createClaimFulfilmentInstruction,
+    createClaimVoucherInstruction,
+    createCloseCampaignInstruction,
+    createCreateCampaignInstruction,
+    createFreezeVoucherInstruction,
+    createInitConfigInstruction,
     createInitEscrowAndMintVoucherInstruction,
+    createInitGrantVaultInstruction,
     createInitMintAuthorityInstruction,
+    createLapseDisputeInstruction,
+    createMigrateEscrowInstruction,
+    createMigrateMintAuthorityInstruction,
+    createOpenDisputeInstruction,
+    createReclaimMilestoneInstruction,
+    createRecoverVoucherInstruction,
+    createRedeemGrantInstruction,
+    createRedeemWithAuthorizationInstruction,
+    createRedeemWithCodeInstruction,
+    createRegisterIssuerInstruction,
+    createRegisterMerchantInstruction,
+    createRejectFulfilmentInstruction,
     createReleaseEscrowAndBurnVoucherInstruction,
     createReleaseExpiredEscrowInstruction,
+    createReleaseFulfilmentInstruction,
+    createResolveDisputeInstruction,
+    createThawVoucherInstruction,
+    createWithdrawGrantVaultInstruction,
 } from './generated';
 import { TokenMetadata } from '@solana/spl-token-metadata';
 import { assert } from 'chai';
@@ -41,13 +72,73 @@ function createKeypairFromFile(path: string): Keypair {
     );
 }
 
+function sha256(...parts: Buffer[]): Buffer {
+    return createHash('sha256').update(Buffer.concat(parts)).digest();
+}
+
+// Little-endian integers, as borsh encodes them
+function u16(value: number): Buffer {
+    const buffer = Buffer.alloc(2);
+    buffer.writeUInt16LE(value);
+    return buffer;
+}
+
+function u32(value: number): Buffer {
+    const buffer = Buffer.alloc(4);
+    buffer.writeUInt32LE(value);
+    return buffer;
+}
+
+function u64(value: number): Buffer {
+    const buffer = Buffer.alloc(8);
+    buffer.writeBigUInt64LE(BigInt(value));
+    return buffer;
+}
+
+function i64(value: number): Buffer {
+    const buffer = Buffer.alloc(8);
+    buffer.writeBigInt64LE(BigInt(value));
+    return buffer;
+}
+
+function now(): number {
+    return Math.floor(Date.now() / 1000);
+}
+
+// Fails unless sending `ixs` is rejected with `log` in the program logs
+async function assertRejected(
+    connection: Connection,
+    ixs: TransactionInstruction[],
+    signers: Signer[],
+    log: string
+) {
+    try {
+        await sendAndConfirmTransaction(
+            connection,
+            new Transaction().add(...ixs),
+            signers
+        );
+    } catch (e) {
+        const logs: string[] = (await e.getLogs(connection)) ?? [];
+        assert(
+            logs.some((line) => line.includes(log)),
+            `Expected "${log}" in logs: ${logs.join('\n')}`
+        );
+        return;
+    }
+    assert.fail(`Transaction should have failed with "${log}"`);
+}
+
 describe('gideon', async () => {
     const title = 'Nike Running Shoes';
     const description = 'Nike running shoes and stuff';
     const symbol = 'NIKE';
     var metadataURI = '';
-    // const expiry = Date.now() + 1000 * 60 * 60 * 24 * 7; // 1 week from now
-    const expiry = Date.now() - 1000 * 60 * 60 * 24 * 30; // 1 day ago
+    // Validity window in unix seconds
+    const validFrom = Math.floor(Date.now() / 1000);
+    // const validUntil = validFrom + 60 * 60 * 24 * 7; // 1 week from now
+    const validUntil = validFrom + 10; // 10 seconds from now, so it can be reclaimed below
+    const gracePeriod = 0;
 
     const connection = new Connection(`http://localhost:8899`, 'confirmed');
     //const connection = new Connection('https://api.devnet.solana.com/', 'confirmed');
@@ -59,13 +150,341 @@ describe('gideon', async () => {
     );
 
     const mintKeypair: Keypair = Keypair.generate();
+    const config = PublicKey.findProgramAddressSync(
+        [Buffer.from('config')],
+        program.publicKey
+    );
+    const programData = PublicKey.findProgramAddressSync(
+        [program.publicKey.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+    );
+
+    const issuer = PublicKey.findProgramAddressSync(
+        [Buffer.from('issuer'), payer.publicKey.toBuffer()],
+        program.publicKey
+    );
+
     const mintAuthority = PublicKey.findProgramAddressSync(
-        [Buffer.from('mint_authority')],
+        [Buffer.from('mint_authority'), issuer[0].toBuffer()],
         program.publicKey
     );
 
     const recipient: Keypair = Keypair.generate();
 
+    // Wallets for the redemption flows below, which issue their own long-lived vouchers
+    const holder: Keypair = Keypair.generate();
+    const merchant: Keypair = Keypair.generate();
+    const arbiter: Keypair = Keypair.generate();
+    const amount = LAMPORTS_PER_SOL / 10;
+
+    const merchantRegistration = PublicKey.findProgramAddressSync(
+        [
+            Buffer.from('merchant'),
+            issuer[0].toBuffer(),
+            merchant.publicKey.toBuffer(),
+        ],
+        program.publicKey
+    );
+    const grantVault = PublicKey.findProgramAddressSync(
+        [Buffer.from('grant_vault'), issuer[0].toBuffer()],
+        program.publicKey
+    );
+
+    type Voucher = {
+        mint: PublicKey;
+        escrow: PublicKey;
+        // The holder's token account
+        ata: PublicKey;
+    };
+
+    function issuerAddresses(authority: PublicKey) {
+        const issuer = PublicKey.findProgramAddressSync(
+            [Buffer.from('issuer'), authority.toBuffer()],
+            program.publicKey
+        );
+        const mintAuthority = PublicKey.findProgramAddressSync(
+            [Buffer.from('mint_authority'), issuer[0].toBuffer()],
+            program.publicKey
+        );
+        return { issuer: issuer[0], mintAuthority: mintAuthority[0] };
+    }
+
+    function escrowAddress(
+        escrowPayer: PublicKey,
+        voucherRecipient: PublicKey,
+        mint: PublicKey
+    ): PublicKey {
+        return PublicKey.findProgramAddressSync(
+            [
+                Buffer.from('escrow'),
+                escrowPayer.toBuffer(),
+                voucherRecipient.toBuffer(),
+                mint.toBuffer(),
+            ],
+            program.publicKey
+        )[0];
+    }
+
+    function milestonesAddress(escrow: PublicKey): PublicKey {
+        return PublicKey.findProgramAddressSync(
+            [Buffer.from('milestones'), escrow.toBuffer()],
+            program.publicKey
+        )[0];
+    }
+
+    // Receipts count redemptions from 1
+    function receiptAddress(mint: PublicKey, redemption: number): PublicKey {
+        return PublicKey.findProgramAddressSync(
+            [Buffer.from('receipt'), mint.toBuffer(), u16(redemption)],
+            program.publicKey
+        )[0];
+    }
+
+    function tokenAddress(mint: PublicKey, owner: PublicKey): PublicKey {
+        return PublicKey.findProgramAddressSync(
+            [
+                owner.toBuffer(),
+                TOKEN_2022_PROGRAM_ID.toBuffer(),
+                mint.toBuffer(),
+            ],
+            ASSOCIATED_TOKEN_PROGRAM_ID
+        )[0];
+    }
+
+    // Issues a single-use voucher for `amount` from `issuerAuthority`'s issuer, minted to
+    // the issuer authority's token account
+    function issueVoucherInstruction(
+        issuerAuthority: PublicKey,
+        voucherRecipient: PublicKey,
+        mint: PublicKey,
+        terms: Partial<InitEscrowArgs> = {}
+    ): TransactionInstruction {
+        const { issuer, mintAuthority } = issuerAddresses(issuerAuthority);
+        const escrow = escrowAddress(issuerAuthority, voucherRecipient, mint);
+        const args: InitEscrowArgs = {
+            payer: issuerAuthority,
+            recipient: voucherRecipient,
+            amount: amount,
+            voucherMint: mint,
+            validFrom: now() - 60,
+            validUntil: now() + 60 * 60 * 24 * 7,
+            gracePeriod: 0,
+            maxRedemptions: 1,
+            perRedemptionAmount: amount,
+            codeHash: null,
+            arbiter: null,
+            arbiterFeeBps: 0,
+            milestones: [],
+            confirmationWindow: 0,
+            refundAddress: null,
+            crankBounty: 0,
+            freezable: false,
+            fiat: null,
+            externalRef: null,
+            ...terms,
+        };
+
+        return createInitEscrowAndMintVoucherInstruction(
+            {
+                escrowAccount: escrow,
+                mintAccount: mint,
+                mintAuthority: mintAuthority,
+                associatedTokenAccount: tokenAddress(mint, issuerAuthority),
+                payer: issuerAuthority,
+                rent: SYSVAR_RENT_PUBKEY,
+                systemProgram: SystemProgram.programId,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                config: config[0],
+                treasury: issuerAuthority,
+                issuer: issuer,
+                milestones:
+                    args.milestones.length > 0
+                        ? milestonesAddress(escrow)
+                        : undefined,
+            },
+            {
+                arg0: args,
+                arg1: {
+                    title: title,
+                    description: description,
+                    symbol: symbol,
+                    uri: metadataURI,
+                    products: [],
+                },
+            }
+        );
+    }
+
+    // Issues a voucher to the holder and hands it over to them
+    async function issueVoucher(
+        terms: Partial<InitEscrowArgs> = {}
+    ): Promise<Voucher> {
+        const mint = Keypair.generate();
+        const ix = issueVoucherInstruction(
+            payer.publicKey,
+            holder.publicKey,
+            mint.publicKey,
+            terms
+        );
+        await send([ix], [payer, mint]);
+
+        const ata = await createAssociatedTokenAccount(
+            connection,
+            payer,
+            mint.publicKey,
+            holder.publicKey,
+            {},
+            TOKEN_2022_PROGRAM_ID,
+            ASSOCIATED_TOKEN_PROGRAM_ID
+        );
+        await transferChecked(
+            connection,
+            payer,
+            tokenAddress(mint.publicKey, payer.publicKey),
+            mint.publicKey,
+            ata,
+            payer,
+            1,
+            0,
+            [],
+            {},
+            TOKEN_2022_PROGRAM_ID
+        );
+
+        return {
+            mint: mint.publicKey,
+            escrow: escrowAddress(
+                payer.publicKey,
+                holder.publicKey,
+                mint.publicKey
+            ),
+            ata: ata,
+        };
+    }
+
+    async function send(
+        ixs: TransactionInstruction[],
+        signers: Signer[]
+    ): Promise<string> {
+        try {
+            return await sendAndConfirmTransaction(
+                connection,
+                new Transaction().add(...ixs),
+                signers
+            );
+        } catch (e) {
+            console.log(await e.getLogs(connection));
+            console.log(e);
+            throw new Error('Failed');
+        }
+    }
+
+    async function voucherBalance(ata: PublicKey): Promise<bigint> {
+        const voucher = await getAccount(
+            connection,
+            ata,
+            undefined,
+            TOKEN_2022_PROGRAM_ID
+        );
+        return voucher.amount;
+    }
+
+    // Redeems the voucher's next use as its holder
+    function redeemInstruction(
+        voucher: Voucher,
+        redemption: number,
+        signer: PublicKey = holder.publicKey
+    ): TransactionInstruction {
+        return createReleaseEscrowAndBurnVoucherInstruction({
+            payer: signer,
+            ata: voucher.ata,
+            mintAccount: voucher.mint,
+            mintAuthority: mintAuthority[0],
+            escrowAccount: voucher.escrow,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            clockProgram: SYSVAR_CLOCK_PUBKEY,
+            systemProgram: SystemProgram.programId,
+            escrowPayer: payer.publicKey,
+            payout: holder.publicKey,
+            config: config[0],
+            treasury: payer.publicKey,
+            receipt: receiptAddress(voucher.mint, redemption),
+        });
+    }
+
+    it('Init Config If Not Exists', async () => {
+        if (await connection.getAccountInfo(config[0])) {
+            console.log('Config Already Exists');
+            return;
+        }
+
+        const ix = createInitConfigInstruction(
+            {
+                config: config[0],
+                upgradeAuthority: payer.publicKey,
+                programData: programData[0],
+                systemProgram: SystemProgram.programId,
+            },
+            {
+                initConfigArgs: {
+                    admin: payer.publicKey,
+                    treasury: payer.publicKey,
+                    issuanceFeeBps: 0,
+                    redemptionFeeBps: 0,
+                    issuanceFlatFee: 0,
+                    redemptionFlatFee: 0,
+                    maxVoucherAmount: 100 * LAMPORTS_PER_SOL,
+                    maxValidityPeriod: 60 * 60 * 24 * 365,
+                    maxGracePeriod: 60 * 60 * 24 * 7,
+                },
+            }
+        );
+
+        const sx = await sendAndConfirmTransaction(
+            connection,
+            new Transaction().add(ix),
+            [payer]
+        );
+
+        console.log('Created Config:');
+        console.log(`   Config: ${config[0]}`);
+        console.log(`   Tx Signature: ${sx}`);
+    });
+
+    it('Register Issuer If Not Exists', async () => {
+        if (await connection.getAccountInfo(issuer[0])) {
+            console.log('Issuer Already Exists');
+            return;
+        }
+
+        const ix = createRegisterIssuerInstruction(
+            {
+                issuer: issuer[0],
+                authority: payer.publicKey,
+                systemProgram: SystemProgram.programId,
+            },
+            {
+                issuerProfileArgs: {
+                    displayName: 'Nike',
+                    logoUri: '',
+                    website: 'https://www.nike.com',
+                    signers: [],
+                },
+            }
+        );
+
+        const sx = await sendAndConfirmTransaction(
+            connection,
+            new Transaction().add(ix),
+            [payer]
+        );
+
+        console.log('Registered Issuer:');
+        console.log(`   Issuer: ${issuer[0]}`);
+        console.log(`   Tx Signature: ${sx}`);
+    });
+
     it('Init Mint Authority If Not Exists', async () => {
         // Check if mint authority exist
         try {
@@ -74,6 +493,7 @@ describe('gideon', async () => {
                 mintAuthority: mintAuthority[0],
                 payer: payer.publicKey,
                 systemProgram: SystemProgram.programId,
+                issuer: issuer[0],
             });
 
             const sx = await sendAndConfirmTransaction(
@@ -105,10 +525,42 @@ describe('gideon', async () => {
         const payer = context.payer;
         const blockhash = context.lastBlockhash;
 
+        // Mint authorities are seeded by issuer, so register one first
+        const issuer = PublicKey.findProgramAddressSync(
+            [Buffer.from('issuer'), payer.publicKey.toBuffer()],
+            program.publicKey
+        );
+        const mintAuthority = PublicKey.findProgramAddressSync(
+            [Buffer.from('mint_authority'), issuer[0].toBuffer()],
+            program.publicKey
+        );
+        const registerTx = new Transaction();
+        registerTx.recentBlockhash = blockhash;
+        registerTx.add(
+            createRegisterIssuerInstruction(
+                {
+                    issuer: issuer[0],
+                    authority: payer.publicKey,
+                    systemProgram: SystemProgram.programId,
+                },
+                {
+                    issuerProfileArgs: {
+                        displayName: 'Nike',
+                        logoUri: '',
+                        website: 'https://www.nike.com',
+                        signers: [],
+                    },
+                }
+            )
+        );
+        registerTx.sign(payer);
+        await client.processTransaction(registerTx);
+
         const ix = createInitMintAuthorityInstruction({
             mintAuthority: mintAuthority[0],
             payer: payer.publicKey,
             systemProgram: SystemProgram.programId,
+            issuer: issuer[0],
         });
 
         const tx = new Transaction();
@@ -210,6 +662,9 @@ describe('gideon', async () => {
                 systemProgram: SystemProgram.programId,
                 tokenProgram: TOKEN_2022_PROGRAM_ID,
                 associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                config: config[0],
+                treasury: payer.publicKey,
+                issuer: issuer[0],
             },
             {
                 arg0: {
@@ -217,13 +672,28 @@ describe('gideon', async () => {
                     recipient: recipient.publicKey,
                     amount: 1 * LAMPORTS_PER_SOL,
                     voucherMint: mintKeypair.publicKey,
+                    validFrom: validFrom,
+                    validUntil: validUntil,
+                    gracePeriod: gracePeriod,
+                    maxRedemptions: 1,
+                    perRedemptionAmount: 1 * LAMPORTS_PER_SOL,
+                    codeHash: null,
+                    arbiter: null,
+                    arbiterFeeBps: 0,
+                    milestones: [],
+                    confirmationWindow: 0,
+                    refundAddress: null,
+                    crankBounty: 0,
+                    freezable: false,
+                    fiat: null,
+                    externalRef: null,
                 },
                 arg1: {
                     title: title,
                     description: description,
                     symbol: symbol,
                     uri: metadataURI,
-                    expiry: expiry,
+                    products: [],
                 },
             }
         );
@@ -292,59 +762,800 @@ describe('gideon', async () => {
         console.log(`   Tx Signature: ${sx}`);
     });
 
-    // it('Release Escrow and Burn Voucher', async () => {
-    //     const ata = await getAssociatedTokenAddress(
-    //         mintKeypair.publicKey,
-    //         recipient.publicKey,
-    //         false,
-    //         TOKEN_2022_PROGRAM_ID,
-    //         ASSOCIATED_TOKEN_PROGRAM_ID
-    //     );
-
-    //     const escrowAccount = PublicKey.findProgramAddressSync(
-    //         [
-    //             Buffer.from('escrow'),
-    //             payer.publicKey.toBuffer(),
-    //             recipient.publicKey.toBuffer(),
-    //             mintKeypair.publicKey.toBuffer(),
-    //         ],
-    //         program.publicKey
-    //     );
-
-    //     const ix = createReleaseEscrowAndBurnVoucherInstruction({
-    //         payer: recipient.publicKey,
-    //         ata: ata,
-    //         mintAccount: mintKeypair.publicKey,
-    //         mintAuthority: mintAuthority[0],
-    //         escrowAccount: escrowAccount[0],
-    //         tokenProgram: TOKEN_2022_PROGRAM_ID,
-    //         clockProgram: SYSVAR_CLOCK_PUBKEY,
-    //         systemProgram: SystemProgram.programId,
-    //     });
-
-    //     console.log('Burning Voucher...');
-    //     console.log(`   Recipient: ${recipient.publicKey}`);
-    //     console.log(`   ATA: ${ata}`);
-    //     console.log(`   Mint: ${mintKeypair.publicKey}`);
-    //     console.log(`   Mint Authority: ${mintAuthority[0]}`);
-    //     console.log(`   Escrow: ${escrowAccount[0]}`);
-
-    //     try {
-    //         const sx = await sendAndConfirmTransaction(
-    //             connection,
-    //             new Transaction().add(ix),
-    //             [recipient]
-    //         );
-    //         console.log('Success!');
-    //         console.log(`   Tx Signature: ${sx}`);
-    //     } catch (e) {
-    //         console.log(await e.getLogs());
-    //         console.log(e);
-    //         throw new Error('Failed');
-    //     }
-    // });
+    it('Fund Test Wallets and Register Merchant', async () => {
+        for (const wallet of [holder, merchant, arbiter]) {
+            const airdropSignature = await connection.requestAirdrop(
+                wallet.publicKey,
+                LAMPORTS_PER_SOL
+            );
+            await connection.confirmTransaction(airdropSignature);
+        }
+
+        const ix = createRegisterMerchantInstruction(
+            {
+                merchantAccount: merchantRegistration[0],
+                authority: payer.publicKey,
+                issuer: issuer[0],
+                systemProgram: SystemProgram.programId,
+            },
+            {
+                registerMerchantArgs: {
+                    merchant: merchant.publicKey,
+                },
+            }
+        );
+
+        const sx = await send([ix], [payer]);
+        console.log('Registered Merchant:');
+        console.log(`   Merchant: ${merchant.publicKey}`);
+        console.log(`   Tx Signature: ${sx}`);
+    });
+
+    it('Release Escrow and Burn Voucher', async () => {
+        // The voucher above expires in seconds to be reclaimed below, so redeem another one
+        const voucher = await issueVoucher();
+
+        console.log('Burning Voucher...');
+        console.log(`   Holder: ${holder.publicKey}`);
+        console.log(`   ATA: ${voucher.ata}`);
+        console.log(`   Mint: ${voucher.mint}`);
+        console.log(`   Mint Authority: ${mintAuthority[0]}`);
+        console.log(`   Escrow: ${voucher.escrow}`);
+
+        // Only the recipient or their delegate can redeem
+        await assertRejected(
+            connection,
+            [redeemInstruction(voucher, 1, merchant.publicKey)],
+            [payer, merchant],
+            'Invalid payer account'
+        );
+
+        const sx = await send([redeemInstruction(voucher, 1)], [payer, holder]);
+        console.log('Success!');
+        console.log(`   Tx Signature: ${sx}`);
+
+        assert(
+            (await voucherBalance(voucher.ata)) === BigInt(0),
+            'Redeemed voucher should be burned'
+        );
+        assert(
+            (await connection.getAccountInfo(voucher.escrow)) === null,
+            'Escrow should be closed'
+        );
+        assert(
+            await connection.getAccountInfo(receiptAddress(voucher.mint, 1)),
+            'Receipt should be created'
+        );
+    });
+
+    it('Approve and Reclaim Milestones', async () => {
+        const voucher = await issueVoucher({
+            milestones: [
+                {
+                    descriptionHash: [...sha256(Buffer.from('Design'))],
+                    amount: amount / 2,
+                    deadline: now() + 60 * 60,
+                },
+                {
+                    descriptionHash: [...sha256(Buffer.from('Build'))],
+                    amount: amount / 2,
+                    deadline: now() + 60 * 60 * 24,
+                },
+            ],
+        });
+        const milestones = milestonesAddress(voucher.escrow);
+
+        const approveIx = createApproveMilestoneInstruction(
+            {
+                payer: payer.publicKey,
+                escrowAccount: voucher.escrow,
+                milestones: milestones,
+                payout: holder.publicKey,
+                config: config[0],
+                treasury: payer.publicKey,
+                receipt: receiptAddress(voucher.mint, 1),
+                systemProgram: SystemProgram.programId,
+                mintAccount: voucher.mint,
+                tokenAccount: voucher.ata,
+                mintAuthority: mintAuthority[0],
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            },
+            {
+                approveMilestoneArgs: {
+                    index: 0,
+                    externalRef: null,
+                },
+            }
+        );
+        const before = await connection.getBalance(holder.publicKey);
+        await send([approveIx], [payer]);
+        assert(
+            (await connection.getBalance(holder.publicKey)) > before,
+            'Approved tranche should be paid out'
+        );
+
+        // The second tranche can only be reclaimed once its deadline has passed
+        const reclaimIx = createReclaimMilestoneInstruction(
+            {
+                payer: payer.publicKey,
+                escrowAccount: voucher.escrow,
+                milestones: milestones,
+                config: config[0],
+                mintAccount: voucher.mint,
+                tokenAccount: voucher.ata,
+                mintAuthority: mintAuthority[0],
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            },
+            {
+                reclaimMilestoneArgs: {
+                    index: 1,
+                },
+            }
+        );
+        await assertRejected(
+            connection,
+            [reclaimIx],
+            [payer],
+            'Milestone not overdue'
+        );
+    });
+
+    it('Open and Resolve Dispute', async () => {
+        const voucher = await issueVoucher({
+            arbiter: arbiter.publicKey,
+        });
+
+        const openIx = (party: PublicKey) =>
+            createOpenDisputeInstruction({
+                party: party,
+                escrowAccount: voucher.escrow,
+            });
+        await assertRejected(
+            connection,
+            [openIx(merchant.publicKey)],
+            [payer, merchant],
+            'Only the payer or recipient can open a dispute'
+        );
+        await send([openIx(holder.publicKey)], [payer, holder]);
+
+        // Disputes only lapse once the arbiter has had DISPUTE_TIMEOUT to rule
+        await assertRejected(
+            connection,
+            [
+                createLapseDisputeInstruction({
+                    escrowAccount: voucher.escrow,
+                    config: config[0],
+                }),
+            ],
+            [payer],
+            'Dispute not timed out'
+        );
+
+        const resolveIx = (toPayer: number, toRecipient: number) =>
+            createResolveDisputeInstruction(
+                {
+                    arbiter: arbiter.publicKey,
+                    escrowAccount: voucher.escrow,
+                    escrowPayer: payer.publicKey,
+                    payout: holder.publicKey,
+                    merchant: merchant.publicKey,
+                    mintAccount: voucher.mint,
+                    tokenAccount: voucher.ata,
+                    mintAuthority: mintAuthority[0],
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                    config: config[0],
+                },
+                {
+                    resolveDisputeArgs: {
+                        toPayer: toPayer,
+                        toRecipient: toRecipient,
+                        toMerchant: 0,
+                    },
+                }
+            );
+        await assertRejected(
+            connection,
+            [resolveIx(amount, amount)],
+            [payer, arbiter],
+            'Invalid dispute settlement'
+        );
+        await send([resolveIx(amount / 2, amount / 2)], [payer, arbiter]);
+
+        assert(
+            (await voucherBalance(voucher.ata)) === BigInt(0),
+            'Settled voucher should be burned'
+        );
+        assert(
+            (await connection.getAccountInfo(voucher.escrow)) === null,
+            'Escrow should be closed'
+        );
+    });
+
+    it('Claim, Reject and Release Fulfilment', async () => {
+        const voucher = await issueVoucher({
+            confirmationWindow: 60 * 60,
+        });
+
+        const claimIx = (claimant: PublicKey, registration: PublicKey) =>
+            createClaimFulfilmentInstruction({
+                merchant: claimant,
+                ata: voucher.ata,
+                mintAccount: voucher.mint,
+                escrowAccount: voucher.escrow,
+                config: config[0],
+                merchantRegistration: registration,
+            });
+        await assertRejected(
+            connection,
+            [claimIx(arbiter.publicKey, merchantRegistration[0])],
+            [payer, arbiter],
+            'Merchant not registered with issuer'
+        );
+
+        // The holder turns down the first claim, then confirms the second
+        await send(
+            [claimIx(merchant.publicKey, merchantRegistration[0])],
+            [payer, merchant]
+        );
+        await send(
+            [
+                createRejectFulfilmentInstruction({
+                    holder: holder.publicKey,
+                    escrowAccount: voucher.escrow,
+                }),
+            ],
+            [payer, holder]
+        );
+        await send(
+            [claimIx(merchant.publicKey, merchantRegistration[0])],
+            [payer, merchant]
+        );
+
+        const releaseIx = (authority: PublicKey) =>
+            createReleaseFulfilmentInstruction({
+                authority: authority,
+                ata: voucher.ata,
+                mintAccount: voucher.mint,
+                mintAuthority: mintAuthority[0],
+                escrowAccount: voucher.escrow,
+                merchant: merchant.publicKey,
+                escrowPayer: payer.publicKey,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                config: config[0],
+                treasury: payer.publicKey,
+                receipt: receiptAddress(voucher.mint, 1),
+                systemProgram: SystemProgram.programId,
+            });
+        await assertRejected(
+            connection,
+            [releaseIx(merchant.publicKey)],
+            [payer, merchant],
+            'Confirmation window still open'
+        );
+
+        const before = await connection.getBalance(merchant.publicKey);
+        await send([releaseIx(holder.publicKey)], [payer, holder]);
+        assert(
+            (await connection.getBalance(merchant.publicKey)) > before,
+            'Merchant should be paid once the holder confirms'
+        );
+        assert(
+            (await voucherBalance(voucher.ata)) === BigInt(0),
+            'Fulfilled voucher should be burned'
+        );
+    });
+
+    it('Init, Withdraw and Redeem Grant Vault', async () => {
+        if (!(await connection.getAccountInfo(grantVault[0]))) {
+            const ix = createInitGrantVaultInstruction(
+                {
+                    grantVault: grantVault[0],
+                    authority: payer.publicKey,
+                    issuer: issuer[0],
+                    systemProgram: SystemProgram.programId,
+                },
+                {
+                    grantVaultArgs: {
+                        title: title,
+                        description: description,
+                        symbol: symbol,
+                        uri: metadataURI,
+                    },
+                }
+            );
+            await send([ix], [payer]);
+        }
+        await send(
+            [
+                SystemProgram.transfer({
+                    fromPubkey: payer.publicKey,
+                    toPubkey: grantVault[0],
+                    lamports: 3 * amount,
+                }),
+            ],
+            [payer]
+        );
+
+        const withdrawIx = (authority: PublicKey) =>
+            createWithdrawGrantVaultInstruction(
+                {
+                    grantVault: grantVault[0],
+                    authority: authority,
+                    issuer: issuer[0],
+                    config: config[0],
+                },
+                {
+                    withdrawGrantVaultArgs: {
+                        amount: amount,
+                    },
+                }
+            );
+        await assertRejected(
+            connection,
+            [withdrawIx(holder.publicKey)],
+            [payer, holder],
+            'Invalid issuer authority'
+        );
+        await send([withdrawIx(payer.publicKey)], [payer]);
+
+        // The issuer signs the grant off-chain, and the holder redeems it
+        const grant = {
+            amount: amount,
+            expiry: now() + 60 * 60 * 24,
+            nonce: Date.now(),
+        };
+        const message = Buffer.concat([
+            Buffer.from('gideon:grant:'),
+            issuer[0].toBuffer(),
+            holder.publicKey.toBuffer(),
+            u64(grant.amount),
+            i64(grant.expiry),
+            u64(grant.nonce),
+        ]);
+        const nonceAccount = PublicKey.findProgramAddressSync(
+            [
+                Buffer.from('grant_nonce'),
+                issuer[0].toBuffer(),
+                u64(grant.nonce),
+            ],
+            program.publicKey
+        );
+        const redeemGrantIxs = (mint: PublicKey) => [
+            Ed25519Program.createInstructionWithPrivateKey({
+                privateKey: payer.secretKey,
+                message: message,
+            }),
+            createRedeemGrantInstruction(
+                {
+                    escrowAccount: escrowAddress(
+                        payer.publicKey,
+                        holder.publicKey,
+                        mint
+                    ),
+                    recipient: holder.publicKey,
+                    mintAccount: mint,
+                    mintAuthority: mintAuthority[0],
+                    associatedTokenAccount: tokenAddress(
+                        mint,
+                        holder.publicKey
+                    ),
+                    rent: SYSVAR_RENT_PUBKEY,
+                    systemProgram: SystemProgram.programId,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    config: config[0],
+                    treasury: payer.publicKey,
+                    issuer: issuer[0],
+                    issuerSigner: payer.publicKey,
+                    grantVault: grantVault[0],
+                    nonceAccount: nonceAccount[0],
+                    instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+                },
+                {
+                    redeemGrantArgs: grant,
+                }
+            ),
+        ];
+
+        const mint = Keypair.generate();
+        await send(redeemGrantIxs(mint.publicKey), [payer, holder, mint]);
+        assert(
+            (await voucherBalance(
+                tokenAddress(mint.publicKey, holder.publicKey)
+            )) === BigInt(1),
+            'Granted voucher should be minted to the holder'
+        );
+
+        // Each grant can only be redeemed once
+        const replayMint = Keypair.generate();
+        await assertRejected(
+            connection,
+            redeemGrantIxs(replayMint.publicKey),
+            [payer, holder, replayMint],
+            'Nonce already used'
+        );
+    });
+
+    it('Create, Claim and Close Campaign', async () => {
+        const campaignId = Date.now();
+        const campaign = PublicKey.findProgramAddressSync(
+            [Buffer.from('campaign'), issuer[0].toBuffer(), u64(campaignId)],
+            program.publicKey
+        );
+
+        // A single leaf is its own root, claimed with an empty proof
+        const expiry = now() + 60 * 60 * 24;
+        const leaf = sha256(
+            Buffer.from([0]),
+            u32(0),
+            holder.publicKey.toBuffer(),
+            u64(amount),
+            i64(expiry)
+        );
+
+        const createIx = createCreateCampaignInstruction(
+            {
+                campaign: campaign[0],
+                authority: payer.publicKey,
+                issuer: issuer[0],
+                config: config[0],
+                treasury: payer.publicKey,
+                systemProgram: SystemProgram.programId,
+            },
+            {
+                createCampaignArgs: {
+                    campaignId: campaignId,
+                    merkleRoot: [...leaf],
+                    leafCount: 1,
+                    totalAmount: amount,
+                    claimUntil: now() + 60 * 60,
+                    gracePeriod: 0,
+                    title: title,
+                    description: description,
+                    symbol: symbol,
+                    uri: metadataURI,
+                },
+            }
+        );
+        await send([createIx], [payer]);
+
+        const claimIx = (mint: PublicKey) =>
+            createClaimVoucherInstruction(
+                {
+                    escrowAccount: escrowAddress(
+                        payer.publicKey,
+                        holder.publicKey,
+                        mint
+                    ),
+                    recipient: holder.publicKey,
+                    mintAccount: mint,
+                    mintAuthority: mintAuthority[0],
+                    associatedTokenAccount: tokenAddress(
+                        mint,
+                        holder.publicKey
+                    ),
+                    rent: SYSVAR_RENT_PUBKEY,
+                    systemProgram: SystemProgram.programId,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    config: config[0],
+                    campaign: campaign[0],
+                },
+                {
+                    claimVoucherArgs: {
+                        index: 0,
+                        amount: amount,
+                        expiry: expiry,
+                        proof: [],
+                    },
+                }
+            );
+
+        const mint = Keypair.generate();
+        await send([claimIx(mint.publicKey)], [payer, holder, mint]);
+        assert(
+            (await voucherBalance(
+                tokenAddress(mint.publicKey, holder.publicKey)
+            )) === BigInt(1),
+            'Claimed voucher should be minted to the holder'
+        );
+
+        const replayMint = Keypair.generate();
+        await assertRejected(
+            connection,
+            [claimIx(replayMint.publicKey)],
+            [payer, holder, replayMint],
+            'Voucher already claimed'
+        );
+
+        // Unclaimed funds only return to the issuer once the claim period is over
+        await assertRejected(
+            connection,
+            [
+                createCloseCampaignInstruction({
+                    authority: payer.publicKey,
+                    campaign: campaign[0],
+                    config: config[0],
+                }),
+            ],
+            [payer],
+            'Campaign claim period not over'
+        );
+    });
+
+    it('Freeze and Thaw Voucher', async () => {
+        const voucher = await issueVoucher({
+            freezable: true,
+        });
+
+        const accounts = {
+            authority: payer.publicKey,
+            escrowAccount: voucher.escrow,
+            mintAccount: voucher.mint,
+            tokenAccount: voucher.ata,
+            mintAuthority: mintAuthority[0],
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            issuer: issuer[0],
+            config: config[0],
+        };
+        await send(
+            [
+                createFreezeVoucherInstruction(accounts, {
+                    freezeVoucherArgs: {
+                        reasonCode: 1,
+                    },
+                }),
+            ],
+            [payer]
+        );
+        const frozen = await getAccount(
+            connection,
+            voucher.ata,
+            undefined,
+            TOKEN_2022_PROGRAM_ID
+        );
+        assert(frozen.isFrozen, 'Voucher should be frozen');
+
+        await send([createThawVoucherInstruction(accounts)], [payer]);
+        const thawed = await getAccount(
+            connection,
+            voucher.ata,
+            undefined,
+            TOKEN_2022_PROGRAM_ID
+        );
+        assert(!thawed.isFrozen, 'Voucher should be thawed');
+
+        await assertRejected(
+            connection,
+            [createThawVoucherInstruction(accounts)],
+            [payer],
+            'Voucher not frozen'
+        );
+    });
+
+    it('Recover Voucher', async () => {
+        const voucher = await issueVoucher();
+        const newHolder = Keypair.generate();
+        const mint = Keypair.generate();
+
+        const recoverIx = (wallet: PublicKey) =>
+            createRecoverVoucherInstruction({
+                escrowAccount: voucher.escrow,
+                authority: payer.publicKey,
+                mintAccount: mint.publicKey,
+                mintAuthority: mintAuthority[0],
+                associatedTokenAccount: tokenAddress(mint.publicKey, wallet),
+                rent: SYSVAR_RENT_PUBKEY,
+                systemProgram: SystemProgram.programId,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                newHolder: wallet,
+                oldMintAccount: voucher.mint,
+                oldTokenAccount: voucher.ata,
+                issuer: issuer[0],
+                config: config[0],
+                admin: payer.publicKey,
+            });
+
+        // Recovery can't be used to claw the voucher back to whoever funded it
+        await assertRejected(
+            connection,
+            [recoverIx(payer.publicKey)],
+            [payer, mint],
+            "Voucher can't be recovered to its payer or issuer"
+        );
+
+        await send([recoverIx(newHolder.publicKey)], [payer, mint]);
+        assert(
+            (await voucherBalance(voucher.ata)) === BigInt(0),
+            'Lost voucher should be burned'
+        );
+        assert(
+            (await voucherBalance(
+                tokenAddress(mint.publicKey, newHolder.publicKey)
+            )) === BigInt(1),
+            'Replacement voucher should be minted to the new holder'
+        );
+    });
+
+    it('Reject Migrating Current Vouchers', async () => {
+        const voucher = await issueVoucher();
+
+        await assertRejected(
+            connection,
+            [
+                createMigrateEscrowInstruction({
+                    funder: payer.publicKey,
+                    escrowAccount: voucher.escrow,
+                    mintAccount: voucher.mint,
+                    systemProgram: SystemProgram.programId,
+                    issuer: issuer[0],
+                }),
+            ],
+            [payer],
+            'Escrow not in the legacy layout'
+        );
+
+        // Already minted under the issuer's mint authority, so there is nothing to migrate
+        await assertRejected(
+            connection,
+            [
+                createMigrateMintAuthorityInstruction({
+                    authority: payer.publicKey,
+                    issuer: issuer[0],
+                    escrowAccount: voucher.escrow,
+                    mintAccount: voucher.mint,
+                    legacyMintAuthority: mintAuthority[0],
+                    mintAuthority: mintAuthority[0],
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                }),
+            ],
+            [payer],
+            'Voucher already uses an issuer mint authority'
+        );
+    });
+
+    it('Redeem With Code', async () => {
+        // Codes are high-entropy random bytes, hashed with the mint they unlock
+        const mint = Keypair.generate();
+        const preimage = randomBytes(32);
+        const ix = issueVoucherInstruction(
+            payer.publicKey,
+            holder.publicKey,
+            mint.publicKey,
+            {
+                codeHash: [...sha256(mint.publicKey.toBuffer(), preimage)],
+            }
+        );
+        await send([ix], [payer, mint]);
+        const ata = tokenAddress(mint.publicKey, payer.publicKey);
+
+        const redeemIx = (code: Buffer) =>
+            createRedeemWithCodeInstruction(
+                {
+                    merchant: merchant.publicKey,
+                    ata: ata,
+                    mintAccount: mint.publicKey,
+                    mintAuthority: mintAuthority[0],
+                    escrowAccount: escrowAddress(
+                        payer.publicKey,
+                        holder.publicKey,
+                        mint.publicKey
+                    ),
+                    escrowPayer: payer.publicKey,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                    config: config[0],
+                    treasury: payer.publicKey,
+                    receipt: receiptAddress(mint.publicKey, 1),
+                    systemProgram: SystemProgram.programId,
+                    merchantRegistration: merchantRegistration[0],
+                },
+                {
+                    redeemWithCodeArgs: {
+                        preimage: code,
+                        product: null,
+                        externalRef: null,
+                    },
+                }
+            );
+        await assertRejected(
+            connection,
+            [redeemIx(randomBytes(32))],
+            [payer, merchant],
+            'Invalid redemption code'
+        );
+
+        const before = await connection.getBalance(merchant.publicKey);
+        await send([redeemIx(preimage)], [payer, merchant]);
+        assert(
+            (await connection.getBalance(merchant.publicKey)) > before,
+            'Merchant should be paid for the code'
+        );
+        assert(
+            (await voucherBalance(ata)) === BigInt(0),
+            'Redeemed voucher should be burned'
+        );
+    });
+
+    it('Redeem With Authorization', async () => {
+        const voucher = await issueVoucher();
+        const nonceAccount = PublicKey.findProgramAddressSync(
+            [Buffer.from('redemption_nonce'), voucher.mint.toBuffer()],
+            program.publicKey
+        );
+
+        // The holder signs the authorization off-chain, and the merchant submits it
+        const authorization = {
+            amount: amount,
+            nonce: 1,
+            deadline: now() + 60 * 10,
+            product: null,
+            externalRef: null,
+        };
+        const message = Buffer.concat([
+            Buffer.from('gideon:redeem:'),
+            program.publicKey.toBuffer(),
+            voucher.mint.toBuffer(),
+            merchant.publicKey.toBuffer(),
+            u64(authorization.amount),
+            u64(authorization.nonce),
+            i64(authorization.deadline),
+            // No product or external reference
+            Buffer.from([0, 0]),
+        ]);
+        const redeemIxs = (signer: Keypair) => [
+            Ed25519Program.createInstructionWithPrivateKey({
+                privateKey: signer.secretKey,
+                message: message,
+            }),
+            createRedeemWithAuthorizationInstruction(
+                {
+                    merchant: merchant.publicKey,
+                    holder: holder.publicKey,
+                    ata: voucher.ata,
+                    mintAccount: voucher.mint,
+                    mintAuthority: mintAuthority[0],
+                    escrowAccount: voucher.escrow,
+                    nonceAccount: nonceAccount[0],
+                    escrowPayer: payer.publicKey,
+                    instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+                    tokenProgram: TOKEN_2022_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    config: config[0],
+                    treasury: payer.publicKey,
+                    receipt: receiptAddress(voucher.mint, 1),
+                },
+                {
+                    redeemWithAuthorizationArgs: authorization,
+                }
+            ),
+        ];
+
+        await assertRejected(
+            connection,
+            redeemIxs(merchant),
+            [payer, merchant],
+            'Ed25519 signature does not match'
+        );
+
+        await send(redeemIxs(holder), [payer, merchant]);
+        assert(
+            (await voucherBalance(voucher.ata)) === BigInt(0),
+            'Redeemed voucher should be burned'
+        );
+        assert(
+            (await connection.getAccountInfo(nonceAccount[0])) === null,
+            'Nonce account should be closed with the escrow'
+        );
+    });
 
     it("Release Expired Escrow", async () => {
+        // Wait until the voucher and its grace period have passed
+        const waitMs = (validUntil + gracePeriod + 2) * 1000 - Date.now();
+        if (waitMs > 0) {
+            await new Promise((resolve) => setTimeout(resolve, waitMs));
+        }
+
         const escrowAccount = PublicKey.findProgramAddressSync(
             [
                 Buffer.from('escrow'),
@@ -355,11 +1566,24 @@ describe('gideon', async () => {
             program.publicKey
         );
 
+        const ataRecipient = await getAssociatedTokenAddress(
+            mintKeypair.publicKey,
+            recipient.publicKey,
+            false,
+            TOKEN_2022_PROGRAM_ID,
+            ASSOCIATED_TOKEN_PROGRAM_ID
+        );
+
         const ix = createReleaseExpiredEscrowInstruction({
-            payer: payer.publicKey,
+            cranker: payer.publicKey,
             escrowAccount: escrowAccount[0],
             mintAccount: mintKeypair.publicKey,
             clockProgram: SYSVAR_CLOCK_PUBKEY,
+            config: config[0],
+            refund: payer.publicKey,
+            tokenAccount: ataRecipient,
+            mintAuthority: mintAuthority[0],
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
         });
 
         console.log('Releasing Expired Escrow...');
@@ -380,5 +1604,255 @@ describe('gideon', async () => {
             console.log(e);
             throw new Error('Failed');
         }
+
+        const voucher = await getAccount(
+            connection,
+            ataRecipient,
+            undefined,
+            TOKEN_2022_PROGRAM_ID
+        );
+        assert(voucher.amount === BigInt(0), 'Expired voucher should be burned');
+    });
+
+    // Starts a bank with the config, an issuer and its mint authority set up for its payer
+    async function startBankrun() {
+        const context = await start(
+            [
+                {
+                    name: 'program/target/so/gideon',
+                    programId: program.publicKey,
+                },
+            ],
+            []
+        );
+        const client = context.banksClient;
+        const payer = context.payer;
+        const { issuer, mintAuthority } = issuerAddresses(payer.publicKey);
+
+        const processInstructions = async (
+            ixs: TransactionInstruction[],
+            signers: Keypair[] = []
+        ) => {
+            const tx = new Transaction();
+            tx.recentBlockhash = context.lastBlockhash;
+            tx.add(...ixs);
+            tx.sign(payer, ...signers);
+            return client.processTransaction(tx);
+        };
+
+        // Make the payer the program's upgrade authority, so it can create the config.
+        // Layout: u32 variant (3 = ProgramData), u64 slot, Option<Pubkey> authority
+        const programDataAccount = await client.getAccount(programData[0]);
+        const data = Buffer.from(programDataAccount?.data ?? Buffer.alloc(45));
+        data.writeUInt32LE(3, 0);
+        data[12] = 1;
+        payer.publicKey.toBuffer().copy(data, 13);
+        context.setAccount(programData[0], {
+            lamports: programDataAccount?.lamports ?? LAMPORTS_PER_SOL,
+            data: data,
+            owner: BPF_LOADER_UPGRADEABLE_PROGRAM_ID,
+            executable: false,
+        });
+
+        await processInstructions([
+            createInitConfigInstruction(
+                {
+                    config: config[0],
+                    upgradeAuthority: payer.publicKey,
+                    programData: programData[0],
+                    systemProgram: SystemProgram.programId,
+                },
+                {
+                    initConfigArgs: {
+                        admin: payer.publicKey,
+                        treasury: payer.publicKey,
+                        issuanceFeeBps: 0,
+                        redemptionFeeBps: 0,
+                        issuanceFlatFee: 0,
+                        redemptionFlatFee: 0,
+                        maxVoucherAmount: 100 * LAMPORTS_PER_SOL,
+                        maxValidityPeriod: 60 * 60 * 24 * 365,
+                        maxGracePeriod: 60 * 60 * 24 * 7,
+                    },
+                }
+            ),
+            createRegisterIssuerInstruction(
+                {
+                    issuer: issuer,
+                    authority: payer.publicKey,
+                    systemProgram: SystemProgram.programId,
+                },
+                {
+                    issuerProfileArgs: {
+                        displayName: 'Nike',
+                        logoUri: '',
+                        website: 'https://www.nike.com',
+                        signers: [],
+                    },
+                }
+            ),
+            createInitMintAuthorityInstruction({
+                mintAuthority: mintAuthority,
+                payer: payer.publicKey,
+                systemProgram: SystemProgram.programId,
+                issuer: issuer,
+            }),
+        ]);
+
+        return {
+            context,
+            client,
+            payer,
+            issuer,
+            mintAuthority,
+            processInstructions,
+        };
+    }
+
+    it('Lapse Dispute Bankrun', async () => {
+        const { context, client, payer, processInstructions } =
+            await startBankrun();
+        const clock = await client.getClock();
+        const issuedAt = Number(clock.unixTimestamp);
+
+        const mint = Keypair.generate();
+        await processInstructions(
+            [
+                issueVoucherInstruction(
+                    payer.publicKey,
+                    holder.publicKey,
+                    mint.publicKey,
+                    {
+                        validFrom: issuedAt - 60,
+                        validUntil: issuedAt + 60 * 60 * 24 * 30,
+                        arbiter: arbiter.publicKey,
+                    }
+                ),
+            ],
+            [mint]
+        );
+        const escrow = escrowAddress(
+            payer.publicKey,
+            holder.publicKey,
+            mint.publicKey
+        );
+        await processInstructions([
+            createOpenDisputeInstruction({
+                party: payer.publicKey,
+                escrowAccount: escrow,
+            }),
+        ]);
+
+        // Skip past the arbiter's 14 days to rule, after which anyone can lapse the dispute
+        context.setClock(
+            new Clock(
+                clock.slot,
+                clock.epochStartTimestamp,
+                clock.epoch,
+                clock.leaderScheduleEpoch,
+                clock.unixTimestamp + BigInt(60 * 60 * 24 * 14 + 1)
+            )
+        );
+        const meta = await processInstructions([
+            createLapseDisputeInstruction({
+                escrowAccount: escrow,
+                config: config[0],
+            }),
+        ]);
+        assert(
+            meta.logMessages.includes('Program log: Lapsing dispute'),
+            'Dispute should lapse'
+        );
+    });
+
+    it('Migrate Legacy Escrow Bankrun', async () => {
+        const {
+            context,
+            client,
+            payer,
+            issuer,
+            mintAuthority,
+            processInstructions,
+        } = await startBankrun();
+
+        // Issue a voucher to the payer, then rewrite its escrow in the legacy layout:
+        // payer, recipient, amount, bump and voucher mint, in 16 bytes reserved for the amount
+        const issuedAt = Number((await client.getClock()).unixTimestamp);
+        const mint = Keypair.generate();
+        await processInstructions(
+            [
+                issueVoucherInstruction(
+                    payer.publicKey,
+                    payer.publicKey,
+                    mint.publicKey,
+                    {
+                        validFrom: issuedAt - 60,
+                        validUntil: issuedAt + 60 * 60 * 24 * 7,
+                    }
+                ),
+            ],
+            [mint]
+        );
+        const [escrow, bump] = PublicKey.findProgramAddressSync(
+            [
+                Buffer.from('escrow'),
+                payer.publicKey.toBuffer(),
+                payer.publicKey.toBuffer(),
+                mint.publicKey.toBuffer(),
+            ],
+            program.publicKey
+        );
+        const escrowAccount = await client.getAccount(escrow);
+        context.setAccount(escrow, {
+            lamports: escrowAccount.lamports,
+            data: Buffer.concat([
+                payer.publicKey.toBuffer(),
+                payer.publicKey.toBuffer(),
+                u64(amount),
+                Buffer.from([bump]),
+                mint.publicKey.toBuffer(),
+                Buffer.alloc(8),
+            ]),
+            owner: program.publicKey,
+            executable: false,
+        });
+
+        await processInstructions([
+            createMigrateEscrowInstruction({
+                funder: payer.publicKey,
+                escrowAccount: escrow,
+                mintAccount: mint.publicKey,
+                systemProgram: SystemProgram.programId,
+                issuer: issuer,
+            }),
+        ]);
+        const migrated = await client.getAccount(escrow);
+        assert(
+            migrated.data.length > 113,
+            'Escrow should be resized to the current layout'
+        );
+
+        // The migrated voucher redeems like any other
+        await processInstructions([
+            createReleaseEscrowAndBurnVoucherInstruction({
+                payer: payer.publicKey,
+                ata: tokenAddress(mint.publicKey, payer.publicKey),
+                mintAccount: mint.publicKey,
+                mintAuthority: mintAuthority,
+                escrowAccount: escrow,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                clockProgram: SYSVAR_CLOCK_PUBKEY,
+                systemProgram: SystemProgram.programId,
+                escrowPayer: payer.publicKey,
+                payout: payer.publicKey,
+                config: config[0],
+                treasury: payer.publicKey,
+                receipt: receiptAddress(mint.publicKey, 1),
+            }),
+        ]);
+        assert(
+            (await client.getAccount(escrow)) === null,
+            'Escrow should be closed once redeemed'
+        );
     });
 });
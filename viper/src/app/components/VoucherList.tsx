@@ -237,7 +237,14 @@ export default function Component(props: VoucherListProps = {
         return expiry > 0 && new Date(expiry) < new Date()
     }
 
-    if (vouchers.length === 0) {
+    // Vouchers whose escrow has been reclaimed are dead, even if the NFT was never burned
+    const isVoucherReclaimed = (voucher: VoucherData) => {
+        return parseFloat(voucher.escrow) === 0
+    }
+
+    const liveVouchers = vouchers.filter((voucher) => !isVoucherReclaimed(voucher))
+
+    if (liveVouchers.length === 0) {
         if (userType === 'payer') {
             return (
                 <div className="w-full px-4 py-8">
@@ -286,7 +293,7 @@ export default function Component(props: VoucherListProps = {
             animate={{ opacity: 1 }}
             transition={{ duration: 0.5 }}
         >
-            {vouchers.map((voucher, index) => {
+            {liveVouchers.map((voucher, index) => {
                 const expired = isVoucherExpired(voucher.expiry)
                 return (
                     <motion.div